@@ -0,0 +1,208 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use robbb_db::auto_slowmode::AutoSlowmode;
+use robbb_util::extensions::ClientContextExt;
+use serenity::{
+    client,
+    model::{channel::Message, id::ChannelId},
+    prelude::Mentionable,
+};
+
+/// Slowmode values in seconds, applied one after another while the message rate stays high.
+const SLOWMODE_STEPS: &[u64] = &[2, 5, 10, 15, 30, 60, 120, 300, 600];
+
+/// Time window over which the message rate of a channel is measured.
+const RATE_WINDOW_SECS: i64 = 60;
+
+/// Minimum time between two increases of the slowmode.
+const STEP_UP_INTERVAL_SECS: i64 = 60;
+
+/// Minimum time the message rate has to stay low before the slowmode gets decreased by one step.
+const STEP_DOWN_INTERVAL_SECS: i64 = 120;
+
+lazy_static::lazy_static! {
+    static ref CHANNEL_STATES: parking_lot::Mutex<HashMap<ChannelId, ChannelState>> =
+        parking_lot::Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    recent_messages: VecDeque<DateTime<Utc>>,
+    /// Slowmode the channel had before we started changing it, if we did.
+    original_slowmode: Option<u64>,
+    /// Index into [SLOWMODE_STEPS] of the currently applied slowmode, if any.
+    step: Option<usize>,
+    last_change: Option<DateTime<Utc>>,
+}
+
+impl ChannelState {
+    fn message_rate(&mut self, now: DateTime<Utc>) -> usize {
+        while self
+            .recent_messages
+            .front()
+            .map_or(false, |x| (now - *x).num_seconds() > RATE_WINDOW_SECS)
+        {
+            self.recent_messages.pop_front();
+        }
+        self.recent_messages.len()
+    }
+
+    fn last_change_older_than(&self, now: DateTime<Utc>, secs: i64) -> bool {
+        self.last_change.map_or(true, |x| (now - x).num_seconds() >= secs)
+    }
+
+    /// Check whether the message rate is high enough for the slowmode to be increased.
+    fn wants_step_up(&mut self, settings: &AutoSlowmode, now: DateTime<Utc>) -> bool {
+        let rate = self.message_rate(now);
+        rate >= settings.threshold as usize
+            && self.last_change_older_than(now, STEP_UP_INTERVAL_SECS)
+    }
+
+    /// Decide whether the slowmode should be increased, returning the new slowmode step.
+    /// Requires [ChannelState::original_slowmode] to be known, as the slowmode never gets
+    /// lowered below what the channel had before.
+    /// The step is only remembered once it got applied, through [ChannelState::commit_step_up].
+    fn step_up(&mut self, settings: &AutoSlowmode, now: DateTime<Utc>) -> Option<usize> {
+        if !self.wants_step_up(settings, now) {
+            return None;
+        }
+        let original_slowmode = self.original_slowmode.unwrap_or(0);
+        let next_step = match self.step {
+            Some(step) => step + 1,
+            None => SLOWMODE_STEPS.iter().position(|x| *x > original_slowmode)?,
+        };
+        if next_step >= SLOWMODE_STEPS.len() || SLOWMODE_STEPS[next_step] > settings.max_slowmode {
+            return None;
+        }
+        Some(next_step)
+    }
+
+    fn commit_step_up(&mut self, step: usize, now: DateTime<Utc>) {
+        self.step = Some(step);
+        self.last_change = Some(now);
+    }
+
+    /// Decide whether the slowmode should be decreased, returning the new slowmode step,
+    /// or `Some(None)` when the slowmode should be reset to its original value.
+    fn step_down(&mut self, settings: &AutoSlowmode, now: DateTime<Utc>) -> Option<Option<usize>> {
+        let step = self.step?;
+        let rate = self.message_rate(now);
+        if rate >= settings.threshold as usize / 2
+            || !self.last_change_older_than(now, STEP_DOWN_INTERVAL_SECS)
+        {
+            return None;
+        }
+        let original_slowmode = self.original_slowmode.unwrap_or(0);
+        // steps at or below the original slowmode are skipped in favor of restoring it
+        self.step = step.checked_sub(1).filter(|x| SLOWMODE_STEPS[*x] > original_slowmode);
+        self.last_change = Some(now);
+        Some(self.step)
+    }
+}
+
+/// Track the message rate of channels with automatic slowmode enabled,
+/// and increase the slowmode of the channel when the rate exceeds the configured threshold.
+#[tracing::instrument(skip_all, fields(msg.channel_id = %msg.channel_id))]
+pub async fn handle_message(ctx: &client::Context, msg: &Message) -> Result<()> {
+    let db = ctx.get_db().await;
+    let settings = match db.get_auto_slowmode(msg.channel_id).await? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+
+    let now = Utc::now();
+    let (wants_step_up, needs_original) = {
+        let mut states = CHANNEL_STATES.lock();
+        let state = states.entry(msg.channel_id).or_default();
+        state.recent_messages.push_back(now);
+        (state.wants_step_up(&settings, now), state.original_slowmode.is_none())
+    };
+    if !wants_step_up {
+        return Ok(());
+    }
+
+    if needs_original {
+        let channel = msg.channel_id.to_channel(&ctx).await?.guild();
+        let original_slowmode = channel.and_then(|x| x.rate_limit_per_user).unwrap_or(0);
+        if let Some(state) = CHANNEL_STATES.lock().get_mut(&msg.channel_id) {
+            state.original_slowmode.get_or_insert(original_slowmode);
+        }
+    }
+
+    let (step, rate) = match CHANNEL_STATES.lock().get_mut(&msg.channel_id) {
+        Some(state) => (state.step_up(&settings, now), state.message_rate(now)),
+        None => return Ok(()),
+    };
+    let step = match step {
+        Some(step) => step,
+        None => return Ok(()),
+    };
+    apply_slowmode(ctx, msg.channel_id, SLOWMODE_STEPS[step], rate, "increased").await?;
+    if let Some(state) = CHANNEL_STATES.lock().get_mut(&msg.channel_id) {
+        state.commit_step_up(step, now);
+    }
+    Ok(())
+}
+
+/// Decrease the slowmode of channels where the message rate has calmed down again.
+/// This is called periodically, as quiet channels might not get any messages that could trigger it.
+#[tracing::instrument(skip_all)]
+pub async fn relax_slowmodes(ctx: &client::Context) -> Result<()> {
+    let db = ctx.get_db().await;
+    let all_settings = db.get_auto_slowmodes().await?;
+    let now = Utc::now();
+
+    let changes = {
+        let mut states = CHANNEL_STATES.lock();
+        // forget about channels that aren't configured anymore
+        states.retain(|channel_id, _| all_settings.contains_key(channel_id));
+        states
+            .iter_mut()
+            .filter_map(|(channel_id, state)| {
+                let settings = all_settings.get(channel_id)?;
+                let step = state.step_down(settings, now)?;
+                let slowmode = match step {
+                    Some(step) => SLOWMODE_STEPS[step],
+                    None => state.original_slowmode.take().unwrap_or(0),
+                };
+                Some((*channel_id, slowmode, state.message_rate(now)))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (channel_id, slowmode, rate) in changes {
+        apply_slowmode(ctx, channel_id, slowmode, rate, "decreased").await?;
+    }
+    Ok(())
+}
+
+async fn apply_slowmode(
+    ctx: &client::Context,
+    channel_id: ChannelId,
+    slowmode: u64,
+    rate: usize,
+    change: &str,
+) -> Result<()> {
+    let config = ctx.get_config().await;
+    channel_id.edit(&ctx, |c| c.rate_limit_per_user(slowmode)).await?;
+    tracing::info!(
+        auto_slowmode.channel_id = %channel_id,
+        auto_slowmode.slowmode = slowmode,
+        auto_slowmode.rate = rate,
+        "Automatically {} slowmode", change
+    );
+    config
+        .log_automod_action(ctx, |e| {
+            e.title(format!("Automatically {} slowmode", change));
+            e.description(format!(
+                "Slowmode in {} is now {}s ({} messages in the last minute)",
+                channel_id.mention(),
+                slowmode,
+                rate
+            ));
+        })
+        .await;
+    Ok(())
+}
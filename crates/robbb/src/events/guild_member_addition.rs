@@ -1,12 +1,179 @@
 use super::*;
 use chrono::{DateTime, Utc};
 use poise::serenity_prelude::{Member, Mentionable};
-use robbb_commands::commands;
+use robbb_commands::{commands, commands::raidmode};
+use robbb_db::raid_mode::RaidModeAction;
 use robbb_util::{
     extensions::{ChannelIdExt, ClientContextExt, UserExt},
     log_error, util,
 };
-use std::time::SystemTime;
+use std::{collections::VecDeque, time::SystemTime};
+
+lazy_static::lazy_static! {
+    static ref RECENT_JOINS: parking_lot::Mutex<RecentJoins> = parking_lot::Mutex::new(RecentJoins::default());
+}
+
+/// Sliding window of recent joins, used to detect join raids.
+#[derive(Debug, Default)]
+struct RecentJoins {
+    /// join time and account creation time of every join within the window
+    joins: VecDeque<(DateTime<Utc>, DateTime<Utc>)>,
+    last_alert: Option<DateTime<Utc>>,
+}
+
+/// Account age distribution of the joins within the raid detection window.
+#[derive(Debug)]
+struct JoinStats {
+    total: usize,
+    young: usize,
+    younger_than_day: usize,
+    younger_than_week: usize,
+    younger_than_month: usize,
+}
+
+impl RecentJoins {
+    /// Record a join. If this pushes the joins within the window over one of the configured thresholds,
+    /// and there hasn't already been an alert within the current window, return the stats for the window.
+    fn record_join(
+        &mut self,
+        config: &Config,
+        join_time: DateTime<Utc>,
+        account_created_at: DateTime<Utc>,
+    ) -> Option<JoinStats> {
+        let window = chrono::Duration::from_std(config.raid_join_window).ok()?;
+        self.joins.push_back((join_time, account_created_at));
+        while self.joins.front().map_or(false, |(time, _)| join_time - *time > window) {
+            self.joins.pop_front();
+        }
+
+        let account_ages =
+            self.joins.iter().map(|(time, created)| *time - *created).collect::<Vec<_>>();
+        let stats = JoinStats {
+            total: account_ages.len(),
+            young: account_ages.iter().filter(|x| **x < config.raid_young_account_age).count(),
+            younger_than_day: account_ages.iter().filter(|x| x.num_days() < 1).count(),
+            younger_than_week: account_ages.iter().filter(|x| x.num_days() < 7).count(),
+            younger_than_month: account_ages.iter().filter(|x| x.num_days() < 30).count(),
+        };
+
+        let threshold_exceeded = stats.total >= config.raid_join_threshold
+            || stats.young >= config.raid_young_join_threshold;
+        let recently_alerted = self.last_alert.map_or(false, |x| join_time - x < window);
+        if threshold_exceeded && !recently_alerted {
+            self.last_alert = Some(join_time);
+            Some(stats)
+        } else {
+            None
+        }
+    }
+}
+
+/// Track the join rate, and alert #bot-auto-mod when it looks like a raid is happening.
+/// If configured, this also automatically enables raid mode.
+#[tracing::instrument(skip_all)]
+async fn handle_raid_detection(ctx: &client::Context, new_member: &Member) -> Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let join_time = new_member.joined_at.map_or_else(Utc::now, |x| *x);
+    let stats =
+        match RECENT_JOINS.lock().record_join(&config, join_time, *new_member.user.created_at()) {
+            Some(stats) => stats,
+            None => return Ok(()),
+        };
+    tracing::info!(raid.stats = ?stats, "Detected a possible raid");
+
+    let raid_mode_active = db.get_active_raid_mode().await?.is_some();
+    let enable_raid_mode = config.raid_auto_raidmode && !raid_mode_active;
+
+    config
+        .log_automod_action(&ctx, |e| {
+            e.title("Possible raid detected");
+            e.color(serenity::utils::Color::from_rgb(251, 73, 52));
+            e.description(format!(
+                "{} members joined within the last {}, {} of which have accounts younger than {} days.",
+                stats.total,
+                humantime::Duration::from(config.raid_join_window),
+                stats.young,
+                config.raid_young_account_age.num_days(),
+            ));
+            e.field(
+                "Account ages",
+                indoc::formatdoc!(
+                    "< 1 day: {}
+                    < 1 week: {}
+                    < 1 month: {}
+                    older: {}",
+                    stats.younger_than_day,
+                    stats.younger_than_week,
+                    stats.younger_than_month,
+                    stats.total - stats.younger_than_month,
+                ),
+                false,
+            );
+            if raid_mode_active {
+                e.field("Raid mode", "Already active", false);
+            } else if enable_raid_mode {
+                e.field("Raid mode", "Enabling automatically", false);
+            } else {
+                e.field("Raid mode", "Use `/raidmode on` to lock down new joins", false);
+            }
+        })
+        .await;
+
+    if enable_raid_mode {
+        let bot_id = ctx.cache.current_user_id();
+        raidmode::enter_raid_mode(
+            ctx,
+            bot_id,
+            config.raid_mode_duration,
+            raidmode::default_raid_mode_action(&config),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// If raid mode is active, quarantine or kick the new member, depending on the raid mode settings.
+/// Returns true if the member got kicked.
+#[tracing::instrument(skip_all)]
+async fn handle_raid_mode(ctx: &client::Context, new_member: &Member) -> Result<bool> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let raid_mode = match db.get_active_raid_mode().await? {
+        Some(raid_mode) => raid_mode,
+        None => return Ok(false),
+    };
+
+    match (raid_mode.action, config.role_quarantine) {
+        (RaidModeAction::Quarantine, Some(role_quarantine)) => {
+            new_member.clone().add_role(&ctx, role_quarantine).await?;
+            db.add_raid_mode_quarantined(raid_mode.id, new_member.user.id).await?;
+            config
+                .log_automod_action(&ctx, |e| {
+                    e.author_user(&new_member.user);
+                    e.title("Raid mode: quarantined new member");
+                    e.description(new_member.user.mention_and_tag());
+                })
+                .await;
+        }
+        _ => {
+            let _ = new_member
+                .user
+                .dm(&ctx, |m| {
+                    m.content("The server is currently locked down because of a raid. Please try joining again later.")
+                })
+                .await;
+            new_member.kick_with_reason(&ctx, "Raid mode is active").await?;
+            config
+                .log_automod_action(&ctx, |e| {
+                    e.author_user(&new_member.user);
+                    e.title("Raid mode: kicked new member");
+                    e.description(new_member.user.mention_and_tag());
+                })
+                .await;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
 
 /// check if there's an active mute of a user that just joined.
 /// if so, reapply the mute and log their mute-evasion attempt in modlog
@@ -40,6 +207,18 @@ pub async fn guild_member_addition(ctx: client::Context, new_member: Member) ->
     }
 
     log_error!(handle_mute_evasion(&ctx, &new_member).await);
+    log_error!(handle_raid_detection(&ctx, &new_member).await);
+    let kicked = match handle_raid_mode(&ctx, &new_member).await {
+        Ok(kicked) => kicked,
+        err => {
+            log_error!(err);
+            false
+        }
+    };
+    // a kicked member doesn't need to be verified anymore
+    if !kicked {
+        log_error!(commands::verification::start_verification(&ctx, &new_member).await);
+    }
 
     config
         .channel_bot_traffic
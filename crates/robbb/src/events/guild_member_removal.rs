@@ -23,5 +23,6 @@ pub async fn guild_member_removal(
         })
         .await?;
     db.rm_highlights_of(user.id).await?;
+    db.remove_pending_verification(user.id).await?;
     Ok(())
 }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use poise::serenity_prelude::ChannelType;
+use robbb_commands::{
+    checks::{self, PermissionLevel},
+    commands::tag,
+};
+use robbb_util::tag_template::{self, TemplateContext};
+
+use super::*;
+
+/// Once the cooldown map grows beyond this size, expired entries get removed.
+const MAX_TRACKED_COOLDOWNS: usize = 10_000;
+
+lazy_static::lazy_static! {
+    /// When an auto-responder last responded in a channel, by auto-responder id.
+    static ref LAST_RESPONSE: parking_lot::Mutex<HashMap<(i64, ChannelId), Instant>> =
+        parking_lot::Mutex::new(HashMap::new());
+}
+
+/// Check whether the auto-responder is still on cooldown in the channel,
+/// and otherwise start a new cooldown.
+fn check_and_start_cooldown(id: i64, channel_id: ChannelId, cooldown: Duration) -> bool {
+    let now = Instant::now();
+    let mut last_response = LAST_RESPONSE.lock();
+    if last_response.get(&(id, channel_id)).map_or(false, |x| now - *x < cooldown) {
+        return true;
+    }
+    last_response.insert((id, channel_id), now);
+    if last_response.len() > MAX_TRACKED_COOLDOWNS {
+        // cooldowns are rarely longer than a day, so this keeps all relevant entries
+        last_response.retain(|_, time| now - *time < Duration::from_secs(60 * 60 * 24));
+    }
+    false
+}
+
+/// Reply to messages matching an auto-responder with its tag.
+/// Returns whether a response was sent.
+#[tracing::instrument(skip_all, fields(auto_responder.id))]
+pub async fn handle_auto_responder(ctx: &client::Context, msg: &Message) -> Result<bool> {
+    // don't respond to bot commands
+    if msg.is_private() || msg.content.starts_with(crate::PREFIX) {
+        return Ok(false);
+    }
+    let db = ctx.get_db().await;
+    let matching = db.get_matching_auto_responders(&msg.content).await?;
+    if matching.is_empty() {
+        return Ok(false);
+    }
+
+    let channel = msg
+        .channel(&ctx)
+        .await
+        .context("Couldn't get channel")?
+        .guild()
+        .context("Couldn't get a guild-channel from the channel")?;
+    if channel.kind == ChannelType::PrivateThread {
+        return Ok(false);
+    }
+    // threads are matched by the channel they were created in as well
+    let parent_channel = match channel.thread_metadata.as_ref().and(channel.parent_id) {
+        Some(parent_id) => parent_id
+            .to_channel(&ctx)
+            .await
+            .context("Couldn't get the parent channel of a thread")?
+            .guild()
+            .context("Couldn't get a guild-channel from the parent channel of a thread")?,
+        None => channel.clone(),
+    };
+    let channel_ids = [Some(channel.id), Some(parent_channel.id), parent_channel.parent_id]
+        .into_iter()
+        .flatten()
+        .unique()
+        .collect_vec();
+
+    let responder = match matching.into_iter().find(|x| x.applies_to_channel(&channel_ids)) {
+        Some(responder) => responder,
+        None => return Ok(false),
+    };
+    if checks::get_permission_level(ctx, &msg.author).await? != PermissionLevel::User {
+        return Ok(false);
+    }
+    tracing::Span::current().record("auto_responder.id", &responder.id);
+    if check_and_start_cooldown(responder.id, msg.channel_id, responder.cooldown) {
+        return Ok(false);
+    }
+
+    let tag = match db.get_tag(&responder.tag).await? {
+        Some(tag) => tag,
+        None => {
+            tracing::warn!(
+                "Auto-responder {} refers to missing tag {}",
+                responder.id,
+                responder.tag
+            );
+            return Ok(false);
+        }
+    };
+    log_error!(db.record_tag_usage(&tag.name, msg.channel_id).await);
+
+    let template_ctx = TemplateContext::new(msg.author.id, msg.channel_id, None);
+    let rendered = tag_template::render_tag_content(&tag.content, tag.templated, &template_ctx);
+
+    // same as in /tag, plain links are sent as-is so discord shows a preview of them
+    let embed = if tag.rich_content.is_none() && util::validate_url(&rendered.text) {
+        None
+    } else {
+        let author = tag.moderator.to_user(&ctx).await?;
+        Some(tag::make_tag_embed(ctx, &tag, &author, &rendered.text).await)
+    };
+    let buttons = tag.rich_content.as_ref().map_or(&[][..], |x| x.buttons.as_slice());
+    msg.channel_id
+        .send_message(&ctx, |m| {
+            m.reference_message(msg);
+            m.allowed_mentions(|a| a.empty_parse());
+            if !buttons.is_empty() {
+                m.set_components(tag::make_link_buttons(buttons));
+            }
+            match embed {
+                Some(embed) => m.set_embed(embed),
+                None => m.content(&rendered.text),
+            }
+        })
+        .await?;
+    Ok(true)
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use poise::serenity_prelude::ChannelType;
+use robbb_db::highlight_digest::{HighlightDigestEntry, HighlightMode};
+use tracing_futures::Instrument;
+
+use crate::highlight_delivery::{self, HighlightNotification};
+
+use super::*;
+
+/// Once the activity maps grow beyond this size, outdated entries get removed.
+const MAX_TRACKED_ENTRIES: usize = 10_000;
+
+lazy_static::lazy_static! {
+    /// When a user last sent a message in a channel.
+    static ref LAST_ACTIVITY: parking_lot::Mutex<HashMap<(UserId, ChannelId), DateTime<Utc>>> =
+        parking_lot::Mutex::new(HashMap::new());
+    /// When a user last got a highlight notification.
+    static ref LAST_NOTIFIED: parking_lot::Mutex<HashMap<UserId, DateTime<Utc>>> =
+        parking_lot::Mutex::new(HashMap::new());
+}
+
+fn is_within(
+    time: Option<&DateTime<Utc>>,
+    now: DateTime<Utc>,
+    window: std::time::Duration,
+) -> bool {
+    time.map_or(false, |x| (now - *x).to_std().map_or(true, |elapsed| elapsed < window))
+}
+
+fn record_activity(msg: &Message, window: std::time::Duration) {
+    let now = Utc::now();
+    let mut last_activity = LAST_ACTIVITY.lock();
+    last_activity.insert((msg.author.id, msg.channel_id), now);
+    if last_activity.len() > MAX_TRACKED_ENTRIES {
+        last_activity.retain(|_, time| is_within(Some(time), now, window));
+    }
+}
+
+/// Check whether a user recently wrote in the channel, meaning they likely follow the conversation.
+fn was_recently_active(
+    user_id: UserId,
+    channel_id: ChannelId,
+    window: std::time::Duration,
+) -> bool {
+    is_within(LAST_ACTIVITY.lock().get(&(user_id, channel_id)), Utc::now(), window)
+}
+
+/// Check whether the user is still on highlight cooldown, and otherwise start a new cooldown.
+fn check_and_start_cooldown(user_id: UserId, cooldown: std::time::Duration) -> bool {
+    let now = Utc::now();
+    let mut last_notified = LAST_NOTIFIED.lock();
+    if is_within(last_notified.get(&user_id), now, cooldown) {
+        return true;
+    }
+    last_notified.insert(user_id, now);
+    if last_notified.len() > MAX_TRACKED_ENTRIES {
+        last_notified.retain(|_, time| is_within(Some(time), now, cooldown));
+    }
+    false
+}
+
+#[tracing::instrument(skip_all, fields(highlights.notified_user_cnt))]
+pub async fn handle_highlighting(ctx: &client::Context, msg: &Message) -> Result<usize> {
+    let (config, db) = ctx.get_config_and_db().await;
+    record_activity(msg, config.highlight_active_window);
+
+    // don't trigger on bot commands
+    if msg.content.starts_with(crate::PREFIX) {
+        tracing::Span::current().record("highlights.notified_user_cnt", &0i32);
+        return Ok(0);
+    }
+
+    let highlights_data = db.get_highlights().await?;
+
+    let highlight_matches = tokio::task::spawn_blocking({
+        let msg_content = msg.content.to_string();
+        move || highlights_data.get_triggers_for_message(&msg_content)
+    })
+    .instrument(tracing::debug_span!("highlights-trigger-check"))
+    .await
+    .context("Failed to get highlight triggers for a message")?;
+
+    if highlight_matches.is_empty() {
+        tracing::Span::current().record("highlights.notified_user_cnt", &0i32);
+        return Ok(0);
+    }
+    // don't highlight in private threads or mod internal channels
+    // We do this after checking for highlights as checking for highlights is a lot
+    // cheaper than potentially sending discord API requests for
+    // a lot of messages, specifically in threads
+    let channel = msg
+        .channel(&ctx)
+        .await
+        .context("Couldn't get channel")?
+        .guild()
+        .context("Couldn't get a guild-channel from the channel")?;
+    if channel.kind == ChannelType::PrivateThread {
+        return Ok(0);
+    }
+
+    // threads inherit the permissions and category of the channel they were created in
+    let is_thread = channel.thread_metadata.is_some();
+    let parent_channel = if is_thread {
+        channel
+            .parent_id
+            .context("Couldn't get the parent channel of a thread")?
+            .to_channel(&ctx)
+            .await
+            .context("Couldn't get the parent channel of a thread")?
+            .guild()
+            .context("Couldn't get a guild-channel from the parent channel of a thread")?
+    } else {
+        channel.clone()
+    };
+    let category_id = parent_channel.parent_id.context("Couldn't get category_id")?;
+    if config.category_mod_private == category_id {
+        return Ok(0);
+    }
+    // the channel itself, followed by its parents
+    let channel_ids =
+        [channel.id, parent_channel.id, category_id].into_iter().unique().collect_vec();
+
+    let mut handled_users = HashSet::new();
+    let mut notified_users = HashSet::new();
+    for (word, highlights) in highlight_matches {
+        let users = highlights
+            .into_iter()
+            .filter(|x| x.applies_to_channel(&channel_ids))
+            .map(|x| x.user)
+            .collect_vec();
+        if users.is_empty() {
+            continue;
+        }
+
+        let mut embed = serenity::builder::CreateEmbed::default();
+        embed
+            .title("Highlight notification")
+            .description(indoc::formatdoc!(
+                "`{}` has been mentioned in {}
+                [link to message]({})
+
+                Don't care about this anymore?
+                Run `!highlights remove {}` in #bot to stop getting these notifications,
+                or `/highlight ignore` to ignore this user or channel.
+                {}",
+                word,
+                msg.channel_id.mention(),
+                msg.link(),
+                word,
+                if is_thread {
+                    "Use `/highlight threads` to stop getting highlighted in threads."
+                } else {
+                    ""
+                }
+            ))
+            .author(|a| {
+                a.name(&msg.author.tag());
+                a.icon_url(&msg.author.face())
+            })
+            .timestamp(&msg.timestamp)
+            .footer(|f| {
+                if is_thread {
+                    f.text(format!("#{} › {}", parent_channel.name, channel.name))
+                } else {
+                    f.text(format!("#{}", channel.name))
+                }
+            });
+
+        tracing::debug!(
+            highlights.word = %word,
+            highlights.users = ?users,
+            highlights.users_count = %users.len(),
+            "Notifying {} users about a mention of the word '{}'", users.len(), word
+        );
+
+        for user_id in users {
+            if user_id == msg.author.id
+                // check if the user has already been notified of another word in this message
+                || handled_users.contains(&user_id)
+                // check if the user is already part of the conversation
+                || was_recently_active(user_id, channel.id, config.highlight_active_window)
+            {
+                continue;
+            }
+            handled_users.insert(user_id);
+
+            // check if the user can read that channel. If they aren't cached, we can't tell.
+            let user_can_see_channel = parent_channel
+                .permissions_for_user(&ctx, user_id)
+                .map_or(false, |x| x.view_channel() && x.read_message_history());
+            if !user_can_see_channel {
+                continue;
+            }
+
+            let ignores = db.get_highlight_ignores(user_id).await?;
+            if ignores.ignores_message(msg.author.id, &channel_ids) {
+                continue;
+            }
+
+            let settings = db.get_highlight_settings(user_id).await?;
+            if is_thread && !settings.thread_highlights {
+                continue;
+            }
+
+            if settings.mode == HighlightMode::Immediate {
+                if check_and_start_cooldown(user_id, config.highlight_cooldown) {
+                    continue;
+                }
+                let notification = HighlightNotification { user_id, embed: embed.clone() };
+                if highlight_delivery::queue_notification(notification) {
+                    notified_users.insert(user_id);
+                }
+            } else {
+                // the cooldown doesn't apply here, as digests are sent as a single message anyways
+                let entry = HighlightDigestEntry {
+                    user: user_id,
+                    trigger: word.clone(),
+                    channel_id: channel.id,
+                    message_link: msg.link(),
+                    author: msg.author.id,
+                    create_date: Utc::now(),
+                };
+                db.add_highlight_digest_entry(entry).await?;
+                notified_users.insert(user_id);
+            }
+        }
+    }
+
+    tracing::Span::current().record("highlights.notified_user_cnt", &notified_users.len());
+    Ok(notified_users.len())
+}
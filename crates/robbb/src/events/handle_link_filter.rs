@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use itertools::Itertools;
+use regex::Regex;
+use robbb_commands::{
+    checks::{self, PermissionLevel},
+    commands,
+};
+use robbb_db::{link_filter::LinkFilterData, mod_action::ModActionKind};
+use robbb_util::config::LinkFilterAction;
+
+use super::*;
+
+/// Maximum amount of distinct invites in a single message that get looked up.
+/// Messages with more invites than that are treated as spam without looking any of them up.
+const MAX_INVITE_LOOKUPS: usize = 5;
+
+/// How long the guild an invite points to is remembered.
+const INVITE_CACHE_SECS: i64 = 60 * 60;
+
+lazy_static::lazy_static! {
+    static ref URL_REGEX: Regex = Regex::new(r"https?://[^\s<>|]+").unwrap();
+    static ref INVITE_REGEX: Regex =
+        Regex::new(r"(?i)(?:discord(?:app)?\.com/invite|discord\.gg)/([a-z0-9-]+)").unwrap();
+    static ref INVITE_CACHE: parking_lot::Mutex<HashMap<String, CachedInvite>> =
+        parking_lot::Mutex::new(HashMap::new());
+}
+
+/// The guild an invite points to, `None` for invalid invites, and when it was looked up.
+type CachedInvite = (Option<(GuildId, String)>, chrono::DateTime<Utc>);
+
+/// What the link filter took as the outcome for a message containing a blocked link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkFilterOutcome {
+    Deleted,
+    Muted,
+    Banned,
+}
+
+/// If the message contains a link to a blocked domain or an invite to a server that isn't allowed,
+/// delete the message and handle the author according to the configured [LinkFilterAction].
+/// Returns true if the message was deleted, in which case handling the message_create event should be stopped early.
+#[tracing::instrument(skip_all, fields(link_filter.reason, msg.author = %msg.author.tag(), %msg.id))]
+pub async fn handle_link_filter(ctx: &client::Context, msg: &Message) -> Result<bool> {
+    let (config, db) = ctx.get_config_and_db().await;
+    if checks::get_permission_level(&ctx, &msg.author).await? != PermissionLevel::User {
+        return Ok(false);
+    }
+    let filter = db.get_link_filter().await?;
+
+    let reason = match find_blocked_link(ctx, &config, &filter, &msg.content).await? {
+        Some(reason) => reason,
+        None => return Ok(false),
+    };
+
+    tracing::info!(link_filter.reason = %reason, "Found blocked link: {}", reason);
+    tracing::Span::current().record("link_filter.reason", &reason.as_str());
+
+    msg.channel_id.delete_message(&ctx, msg.id).await?;
+
+    let _ = msg
+        .author
+        .dm(&ctx, |m| {
+            m.embed(|e| {
+                e.description(&msg.content)
+                    .title(format!("Your message has been deleted for containing a {}", reason))
+            })
+        })
+        .await;
+
+    let account_age = Utc::now() - *msg.author.created_at();
+    let bot_id = ctx.cache.current_user_id();
+    let outcome = match config.link_filter_action {
+        LinkFilterAction::Ban if account_age < config.link_filter_new_account_age => {
+            let ban_reason = format!("Posted a {}", reason);
+            config.guild.ban_with_reason(&ctx, &msg.author, 1, &ban_reason).await?;
+            db.add_mod_action(
+                bot_id,
+                msg.author.id,
+                ban_reason,
+                Utc::now(),
+                msg.link(),
+                ModActionKind::Ban,
+            )
+            .await?;
+            LinkFilterOutcome::Banned
+        }
+        LinkFilterAction::Mute | LinkFilterAction::Ban => {
+            let member = config.guild.member(&ctx, msg.author.id).await?;
+            commands::mute::apply_mute(
+                ctx,
+                bot_id,
+                member,
+                config.link_filter_mute_duration,
+                Some(format!("Posted a {}", reason)),
+                msg.link(),
+            )
+            .await?;
+            LinkFilterOutcome::Muted
+        }
+        LinkFilterAction::Delete => {
+            db.add_mod_action(
+                bot_id,
+                msg.author.id,
+                format!("Message deleted for containing a {}", reason),
+                Utc::now(),
+                msg.link(),
+                ModActionKind::BlocklistViolation,
+            )
+            .await?;
+            LinkFilterOutcome::Deleted
+        }
+    };
+
+    config
+        .log_automod_action(&ctx, |e| {
+            e.author_user(&msg.author);
+            e.title("Link filter");
+            e.field("Deleted because of", &reason, false);
+            e.field(
+                "Action",
+                match outcome {
+                    LinkFilterOutcome::Deleted => "Message deleted".to_string(),
+                    LinkFilterOutcome::Muted => format!(
+                        "Muted for {}",
+                        humantime::Duration::from(config.link_filter_mute_duration)
+                    ),
+                    LinkFilterOutcome::Banned => "Banned (new account)".to_string(),
+                },
+                false,
+            );
+            e.description(format!("{}\n{}", msg.content, msg.to_context_link()));
+        })
+        .await;
+
+    Ok(true)
+}
+
+/// Check the links and invites in a message against the link filter,
+/// returning a description of the first blocked link found.
+async fn find_blocked_link(
+    ctx: &client::Context,
+    config: &Config,
+    filter: &LinkFilterData,
+    content: &str,
+) -> Result<Option<String>> {
+    let blocked_domain = URL_REGEX
+        .find_iter(content)
+        .map(|x| x.as_str().trim_end_matches(|c| matches!(c, ')' | '.' | ',' | '>' | '*' | '_')))
+        .filter_map(util::url_domain)
+        .find(|x| filter.is_domain_blocked(x));
+    if let Some(domain) = blocked_domain {
+        return Ok(Some(format!("link to a blocked domain: `{}`", domain)));
+    }
+
+    let invite_codes = INVITE_REGEX
+        .captures_iter(content)
+        .filter_map(|x| x.get(1))
+        .map(|x| x.as_str())
+        .unique()
+        .collect_vec();
+    if invite_codes.len() > MAX_INVITE_LOOKUPS {
+        return Ok(Some(format!("large amount of invites ({})", invite_codes.len())));
+    }
+
+    for invite_code in invite_codes {
+        // invalid or expired invites are harmless, so we only care about those we can resolve
+        if let Some((guild_id, guild_name)) = get_invite_guild(ctx, invite_code).await? {
+            if guild_id != config.guild && !filter.allowed_invite_guilds.contains(&guild_id) {
+                return Ok(Some(format!(
+                    "invite to another server: {} ({})",
+                    guild_name, guild_id
+                )));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Look up the guild an invite points to, using [INVITE_CACHE] to avoid repeated requests.
+/// Returns `None` for unknown invites. Other errors, like rate limits, are never cached.
+async fn get_invite_guild(
+    ctx: &client::Context,
+    invite_code: &str,
+) -> Result<Option<(GuildId, String)>> {
+    let now = Utc::now();
+    {
+        let mut cache = INVITE_CACHE.lock();
+        cache.retain(|_, (_, time)| (now - *time).num_seconds() < INVITE_CACHE_SECS);
+        if let Some((guild, _)) = cache.get(invite_code) {
+            return Ok(guild.clone());
+        }
+    }
+
+    let guild = match ctx.http.get_invite(invite_code, false, false, None).await {
+        Ok(invite) => invite.guild.map(|guild| (guild.id, guild.name)),
+        Err(err) if util::is_not_found(&err) => None,
+        Err(err) => return Err(err.into()),
+    };
+    INVITE_CACHE.lock().insert(invite_code.to_string(), (guild.clone(), now));
+    Ok(guild)
+}
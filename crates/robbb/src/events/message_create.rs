@@ -1,5 +1,3 @@
-use std::collections::HashSet;
-
 use chrono::Utc;
 use itertools::Itertools;
 use maplit::hashmap;
@@ -10,7 +8,7 @@ use robbb_db::fetch_field::FetchField;
 use tracing::debug;
 use tracing_futures::Instrument;
 
-use crate::attachment_logging;
+use crate::{attachment_logging, auto_slowmode};
 
 use super::*;
 
@@ -46,6 +44,13 @@ pub async fn message_create(ctx: client::Context, msg: Message) -> Result<bool>
         }
     }
 
+    if !msg.is_private() {
+        log_error!(
+            "Error while handling auto slowmode",
+            auto_slowmode::handle_message(&ctx, &msg).await
+        );
+    }
+
     match handle_spam_protect(&ctx, &msg).await {
         Ok(stop) => {
             tracing::Span::current().record("message_create.stopped_at_spam_protect", &stop);
@@ -64,14 +69,30 @@ pub async fn message_create(ctx: client::Context, msg: Message) -> Result<bool>
         }
         err => log_error!("error while handling blocklist", err),
     };
+    match handle_link_filter::handle_link_filter(&ctx, &msg).await {
+        Ok(stop) => {
+            tracing::Span::current().record("message_create.stopped_at_link_filter", &stop);
+            if stop {
+                return Ok(true);
+            }
+        }
+        err => log_error!("error while handling link filter", err),
+    };
 
-    match handle_highlighting(&ctx, &msg).await {
+    match handle_highlighting::handle_highlighting(&ctx, &msg).await {
         Ok(notified_users) => {
             tracing::Span::current().record("message_create.notified_user_cnt", &notified_users);
         }
         err => log_error!("error while checking/handling highlights", err),
     }
 
+    match handle_auto_responder::handle_auto_responder(&ctx, &msg).await {
+        Ok(responded) => {
+            tracing::Span::current().record("message_create.auto_responded", &responded);
+        }
+        err => log_error!("error while handling auto-responders", err),
+    }
+
     match handle_quote(&ctx, &msg).await {
         Ok(stop) => {
             tracing::Span::current().record("message_create.stopped_at_quote", &stop);
@@ -115,109 +136,6 @@ async fn handle_techsupport_post(ctx: client::Context, msg: &Message) -> Result<
     Ok(())
 }
 
-#[tracing::instrument(skip_all, fields(highlights.notified_user_cnt))]
-async fn handle_highlighting(ctx: &client::Context, msg: &Message) -> Result<usize> {
-    // don't trigger on bot commands
-    if msg.content.starts_with('!') {
-        tracing::Span::current().record("highlights.notified_user_cnt", &0i32);
-        return Ok(0);
-    }
-
-    let (config, db) = ctx.get_config_and_db().await;
-
-    let highlights_data = db.get_highlights().await?;
-
-    let highlight_matches = tokio::task::spawn_blocking({
-        let msg_content = msg.content.to_string();
-        move || highlights_data.get_triggers_for_message(&msg_content)
-    })
-    .instrument(tracing::debug_span!("highlights-trigger-check"))
-    .await
-    .context("Failed to get highlight triggers for a message")?;
-
-    if highlight_matches.is_empty() {
-        tracing::Span::current().record("highlights.notified_user_cnt", &0i32);
-        return Ok(0);
-    }
-    // don't highlight in threads or mod internal channels
-    // We do this after checking for highlights as checking for highlights is a lot
-    // cheaper than potentially sending discord API requests for
-    // a lot of messages, specifically in threads
-    let channel = msg
-        .channel(&ctx)
-        .await
-        .context("Couldn't get channel")?
-        .guild()
-        .context("Couldn't get a guild-channel from the channel")?;
-    if channel.thread_metadata.is_some()
-        || config.category_mod_private == channel.parent_id.context("Couldn't get category_id")?
-    {
-        return Ok(0);
-    }
-
-    let mut handled_users = HashSet::new();
-    for (word, users) in highlight_matches {
-        let mut embed = serenity::builder::CreateEmbed::default();
-        embed
-            .title("Highlight notification")
-            .description(indoc::formatdoc!(
-                "`{}` has been mentioned in {}
-                [link to message]({})
-
-                Don't care about this anymore?
-                Run `!highlights remove {}` in #bot to stop getting these notifications.",
-                word,
-                msg.channel_id.mention(),
-                msg.link(),
-                word
-            ))
-            .author(|a| {
-                a.name(&msg.author.tag());
-                a.icon_url(&msg.author.face())
-            })
-            .timestamp(&msg.timestamp)
-            .footer(|f| f.text(format!("#{}", channel.name)));
-
-        tracing::debug!(
-            highlights.word = %word,
-            highlights.users = ?users,
-            highlights.users_count = %users.len(),
-            "Notifying {} users about a mention of the word '{}'", users.len(), word
-        );
-
-        for user_id in users {
-            let user_can_see_channel = channel
-                .guild_id
-                .member(&ctx, msg.author.id)
-                .await?
-                .permissions(&ctx)?
-                .read_message_history();
-
-            if user_id == msg.author.id
-                // check if the user has already been notified of another word in this message
-                || handled_users.contains(&user_id)
-                // check if the user can read that channel
-                || !user_can_see_channel
-            {
-                continue;
-            }
-            handled_users.insert(user_id);
-
-            if let Ok(dm_channel) = user_id.create_dm_channel(&ctx).await {
-                let ctx = ctx.clone();
-                let embed = embed.clone();
-                tokio::spawn(async move {
-                    let _ = dm_channel.send_message(&ctx, |m| m.set_embed(embed)).await;
-                })
-                .instrument(tracing::info_span!("send_highlight_notification"));
-            }
-        }
-    }
-
-    tracing::Span::current().record("highlights.notified_user_cnt", &handled_users.len());
-    Ok(handled_users.len())
-}
-
 #[tracing::instrument(skip_all, fields(msg_emoji_logging.emoji_used, guild_id = ?msg.guild_id))]
 async fn handle_msg_emoji_logging(ctx: &client::Context, msg: &Message) -> Result<usize> {
     let actual_emojis = util::find_emojis(&msg.content);
@@ -286,7 +204,7 @@ async fn handle_quote(ctx: &client::Context, msg: &Message) -> Result<bool> {
     lazy_static::lazy_static! {
         static ref MSG_LINK_PATTERN: Regex = Regex::new(r#"<?https://(?:canary|ptb\.)?discord(?:app)?\.com/channels/(\d+)/(\d+)/(\d+)>?"#).unwrap();
     }
-    if msg.content.starts_with('!') {
+    if msg.content.starts_with(crate::PREFIX) {
         return Ok(false);
     }
 
@@ -452,6 +370,7 @@ async fn handle_showcase_post(ctx: &client::Context, msg: &Message) -> Result<()
                 db.update_fetch(
                     msg.author.id,
                     hashmap! { FetchField::Image => attachment.url.to_string() },
+                    false,
                 )
                 .await?;
             }
@@ -33,7 +33,7 @@ pub async fn message_delete(
         return Ok(());
     }
 
-    if msg.content.starts_with('!') {
+    if msg.content.starts_with(crate::PREFIX) {
         let close_messages =
             msg.channel_id.messages(&ctx, |m| m.after(deleted_message_id).limit(5)).await?;
         let bot_reply = close_messages.iter().find(|x| {
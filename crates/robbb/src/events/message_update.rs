@@ -20,10 +20,20 @@ pub async fn message_update(
     let mut msg = event.channel_id.message(&ctx, event.id).await?;
     msg.guild_id = event.guild_id;
 
-    match handle_blocklist::handle_blocklist(&ctx, &msg).await {
-        Ok(false) => {}
-        err => log_error!("error while handling blocklist in message_update", err),
+    let blocked = match handle_blocklist::handle_blocklist(&ctx, &msg).await {
+        Ok(blocked) => blocked,
+        err => {
+            log_error!("error while handling blocklist in message_update", err);
+            false
+        }
     };
+    // links can be edited into a message just as well, so they're checked again
+    if !blocked {
+        match handle_link_filter::handle_link_filter(&ctx, &msg).await {
+            Ok(_) => {}
+            err => log_error!("error while handling link filter in message_update", err),
+        };
+    }
 
     let channel_name =
         util::channel_name(&ctx, event.channel_id).await.unwrap_or_else(|_| "unknown".to_string());
@@ -17,7 +17,10 @@ use serenity::client;
 mod guild_member_addition;
 mod guild_member_removal;
 mod guild_member_update;
+mod handle_auto_responder;
 mod handle_blocklist;
+mod handle_highlighting;
+mod handle_link_filter;
 mod message_create;
 mod message_delete;
 mod message_update;
@@ -96,7 +99,9 @@ impl client::EventHandler for Handler {
         skip_all,
         fields(
             command_name, message_create.notified_user_cnt, message_create.stopped_at_spam_protect,
-            message_create.stopped_at_blocklist, message_create.stopped_at_quote, message_create.emoji_used,
+            message_create.stopped_at_blocklist, message_create.stopped_at_link_filter,
+            message_create.stopped_at_quote, message_create.emoji_used,
+            message_create.auto_responded,
             %msg.content, msg.author = %msg.author.tag(), %msg.channel_id, %msg.id
         )
     )]
@@ -172,6 +177,17 @@ impl client::EventHandler for Handler {
         log_error!(
             robbb_commands::commands::ask::handle_ask_button_clicked(&ctx, &interaction).await
         );
+        log_error!(
+            robbb_commands::commands::verification::handle_verification_button_clicked(
+                &ctx,
+                &interaction
+            )
+            .await
+        );
+        log_error!(
+            robbb_commands::commands::tag::handle_tag_proposal_button_clicked(&ctx, &interaction)
+                .await
+        );
 
         if !stop_event_handler {
             self.dispatch_poise_event(&ctx, &poise::Event::InteractionCreate { interaction }).await;
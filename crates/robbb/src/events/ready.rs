@@ -24,6 +24,13 @@ pub async fn ready(ctx: client::Context, _data_about_bot: Ready) -> Result<()> {
     dehoist_everyone(ctx.clone(), config.guild).await;
 
     start_mute_handler(ctx.clone()).await;
+    start_raid_mode_handler(ctx.clone()).await;
+    start_lockdown_handler(ctx.clone()).await;
+    start_auto_slowmode_handler(ctx.clone()).await;
+    start_verification_timeout_handler(ctx.clone()).await;
+    start_highlight_delivery_handler(ctx.clone()).await;
+    start_highlight_digest_handler(ctx.clone()).await;
+    start_fetch_stats_handler(ctx.clone()).await;
     start_attachment_log_handler(ctx).await;
     Ok(())
 }
@@ -67,6 +74,139 @@ async fn start_mute_handler(ctx: client::Context) {
     });
 }
 
+async fn start_raid_mode_handler(ctx: client::Context) {
+    let db = ctx.get_db().await;
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            let raid_modes = match db.get_newly_expired_raid_modes().await {
+                Ok(raid_modes) => raid_modes,
+                Err(err) => {
+                    tracing::error!(error.message = %err, "Failed to request expired raid modes: {}", err);
+                    continue;
+                }
+            };
+            for raid_mode in raid_modes {
+                log_error!(
+                    "Error ending raid mode",
+                    robbb_commands::commands::raidmode::end_raid_mode(&ctx, &raid_mode).await
+                );
+            }
+        }
+    });
+}
+
+async fn start_lockdown_handler(ctx: client::Context) {
+    let db = ctx.get_db().await;
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            let lockdowns = match db.get_newly_expired_lockdowns().await {
+                Ok(lockdowns) => lockdowns,
+                Err(err) => {
+                    tracing::error!(error.message = %err, "Failed to request expired lockdowns: {}", err);
+                    continue;
+                }
+            };
+            for lockdown in lockdowns {
+                let result =
+                    robbb_commands::commands::lockdown::unlock_channel_lockdown(&ctx, &lockdown)
+                        .await;
+                if let Err(err) = result {
+                    tracing::error!(error.message = %err, "Error handling lockdown removal: {}", err);
+                } else {
+                    modlog::log_lockdown_ended(&ctx, &lockdown).await;
+                }
+            }
+        }
+    });
+}
+
+async fn start_auto_slowmode_handler(ctx: client::Context) {
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            log_error!(
+                "Failed to relax automatic slowmodes",
+                crate::auto_slowmode::relax_slowmodes(&ctx).await
+            );
+        }
+    });
+}
+
+async fn start_verification_timeout_handler(ctx: client::Context) {
+    let db = ctx.get_db().await;
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            let pending = match db.get_expired_pending_verifications().await {
+                Ok(pending) => pending,
+                Err(err) => {
+                    tracing::error!(error.message = %err, "Failed to request expired verifications: {}", err);
+                    continue;
+                }
+            };
+            for verification in pending {
+                log_error!(
+                    "Error kicking unverified member",
+                    robbb_commands::commands::verification::kick_unverified_member(
+                        &ctx,
+                        verification.user,
+                        "Did not verify in time"
+                    )
+                    .await
+                );
+            }
+        }
+    });
+}
+
+async fn start_highlight_delivery_handler(ctx: client::Context) {
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        crate::highlight_delivery::run_delivery_queue(ctx).await;
+    });
+}
+
+async fn start_highlight_digest_handler(ctx: client::Context) {
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            log_error!(
+                "Failed to send highlight digests",
+                crate::highlight_delivery::send_due_digests(&ctx).await
+            );
+        }
+    });
+}
+
+async fn start_fetch_stats_handler(ctx: client::Context) {
+    let db = ctx.get_db().await;
+    tokio::spawn(async move {
+        let _ =
+            tracing_honeycomb::register_dist_tracing_root(tracing_honeycomb::TraceId::new(), None);
+        // record right away, so that frequent restarts don't keep a day from being recorded
+        loop {
+            log_error!(
+                "Failed to record fetch stats",
+                robbb_commands::commands::top::record_fetch_stats(&db).await
+            );
+            tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+        }
+    });
+}
+
 async fn start_attachment_log_handler(ctx: client::Context) {
     let config = ctx.get_config().await;
     tokio::spawn(async move {
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use itertools::Itertools;
+use robbb_db::highlight_digest::HighlightDigestEntry;
+use robbb_util::{extensions::ClientContextExt, util};
+use serenity::{
+    builder::CreateEmbed, client, http::StatusCode, model::id::UserId, prelude::Mentionable,
+};
+use tokio::sync::mpsc;
+
+/// Maximum amount of notifications waiting to be sent. Further notifications get dropped.
+const QUEUE_CAPACITY: usize = 500;
+
+/// Minimum time between two highlight DMs, to stay clear of discord's rate limits.
+const SEND_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often sending a notification is attempted before giving up on it.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Amount of notifications in a row that have to fail before a user's highlights get disabled.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// Maximum amount of message links listed per channel in a digest.
+const MAX_DIGEST_LINKS_PER_CHANNEL: usize = 5;
+
+/// Maximum length of all the fields of a digest, staying below discords limit for embeds.
+const MAX_DIGEST_LENGTH: usize = 5000;
+
+#[derive(Debug)]
+pub struct HighlightNotification {
+    pub user_id: UserId,
+    pub embed: CreateEmbed,
+}
+
+lazy_static::lazy_static! {
+    static ref QUEUE: (
+        mpsc::Sender<HighlightNotification>,
+        parking_lot::Mutex<Option<mpsc::Receiver<HighlightNotification>>>,
+    ) = {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        (sender, parking_lot::Mutex::new(Some(receiver)))
+    };
+
+    /// Amount of notifications that failed in a row, per user.
+    static ref CONSECUTIVE_FAILURES: parking_lot::Mutex<HashMap<UserId, u32>> =
+        parking_lot::Mutex::new(HashMap::new());
+}
+
+/// Queue a highlight notification to be sent by [run_delivery_queue].
+/// Returns false if the queue is full and the notification got dropped.
+pub fn queue_notification(notification: HighlightNotification) -> bool {
+    match QUEUE.0.try_send(notification) {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::warn!("Dropping highlight notification: {}", err);
+            false
+        }
+    }
+}
+
+/// Send the queued highlight notifications one after another.
+/// Only the first call actually processes the queue, any further calls return immediately.
+pub async fn run_delivery_queue(ctx: client::Context) {
+    let mut receiver = match QUEUE.1.lock().take() {
+        Some(receiver) => receiver,
+        None => return,
+    };
+    while let Some(notification) = receiver.recv().await {
+        if let Err(err) = deliver(&ctx, notification).await {
+            tracing::error!(error.message = %err, "Failed to deliver highlight notification: {}", err);
+        }
+        tokio::time::sleep(SEND_INTERVAL).await;
+    }
+}
+
+/// Queue the digests of all users that are due to get theirs.
+#[tracing::instrument(skip_all)]
+pub async fn send_due_digests(ctx: &client::Context) -> Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    for user_id in db.get_users_with_due_highlight_digest().await? {
+        let entries = db.get_highlight_digest(user_id).await?;
+        let last_entry = match entries.last() {
+            Some(entry) => entry.create_date,
+            None => continue,
+        };
+        // users that left the server don't get digests anymore, so their matches are dropped
+        match config.guild.member(&ctx, user_id).await {
+            Ok(_) => {}
+            Err(err) if util::is_not_found(&err) => {
+                db.clear_highlight_digest(user_id, last_entry).await?;
+                continue;
+            }
+            Err(err) => {
+                // the digest is kept and retried in the next run
+                tracing::error!(error.message = %err, "Failed to fetch member for highlight digest: {}", err);
+                continue;
+            }
+        }
+
+        // if the queue is full, the matches are kept for the next attempt
+        let queued =
+            queue_notification(HighlightNotification { user_id, embed: build_digest(&entries) });
+        if queued {
+            db.clear_highlight_digest(user_id, last_entry).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Build a digest embed, listing the message links grouped by trigger and channel.
+fn build_digest(entries: &[HighlightDigestEntry]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("Highlight digest");
+    embed.description(format!(
+        "Your highlights have been mentioned {} times since your last digest.",
+        entries.len()
+    ));
+
+    let mut total_length = 0;
+    let by_trigger = entries.iter().into_group_map_by(|x| x.trigger.as_str());
+    for (trigger, entries) in by_trigger.into_iter().sorted_by_key(|(trigger, _)| *trigger) {
+        let mut value = String::new();
+        let by_channel = entries.into_iter().into_group_map_by(|x| x.channel_id);
+        for (channel_id, entries) in by_channel.into_iter().sorted_by_key(|(x, _)| *x) {
+            let links = entries
+                .iter()
+                .take(MAX_DIGEST_LINKS_PER_CHANNEL)
+                .enumerate()
+                .map(|(idx, x)| format!("[{}]({})", idx + 1, x.message_link))
+                .join(" ");
+            let mut line = format!("{}: {}", channel_id.mention(), links);
+            if entries.len() > MAX_DIGEST_LINKS_PER_CHANNEL {
+                line.push_str(&format!(
+                    " and {} more",
+                    entries.len() - MAX_DIGEST_LINKS_PER_CHANNEL
+                ));
+            }
+            if value.len() + line.len() + 1 > 1024 {
+                break;
+            }
+            value.push_str(&line);
+            value.push('\n');
+        }
+
+        // field names are limited to 256 characters
+        let name = format!("`{}`", util::ellipsis_text(trigger, 250));
+        total_length += name.len() + value.len();
+        if total_length > MAX_DIGEST_LENGTH {
+            embed.footer(|f| f.text("Some mentions didn't fit into this digest"));
+            break;
+        }
+        embed.field(name, value, false);
+    }
+    embed
+}
+
+#[tracing::instrument(skip_all, fields(highlights.user_id = %notification.user_id))]
+async fn deliver(ctx: &client::Context, notification: HighlightNotification) -> Result<()> {
+    let user_id = notification.user_id;
+    let mut attempt = 1;
+    let error = loop {
+        match send_dm(ctx, &notification).await {
+            Ok(()) => {
+                CONSECUTIVE_FAILURES.lock().remove(&user_id);
+                return Ok(());
+            }
+            // the user doesn't accept DMs from us, retrying won't help
+            Err(err) if is_forbidden(&err) => break err,
+            Err(err) if attempt >= MAX_ATTEMPTS => break err,
+            Err(err) => {
+                tracing::debug!("Retrying highlight notification after error: {}", err);
+                tokio::time::sleep(SEND_INTERVAL * 4 * attempt).await;
+                attempt += 1;
+            }
+        }
+    };
+
+    let failures = {
+        let mut failures = CONSECUTIVE_FAILURES.lock();
+        let count = failures.entry(user_id).or_default();
+        *count += 1;
+        *count
+    };
+    tracing::info!(
+        highlights.failures = failures,
+        "Failed to send highlight notification: {}",
+        error
+    );
+
+    if failures >= MAX_CONSECUTIVE_FAILURES {
+        CONSECUTIVE_FAILURES.lock().remove(&user_id);
+        let reason = if is_forbidden(&error) {
+            "I wasn't allowed to send you DMs".to_string()
+        } else {
+            format!("sending you DMs kept failing ({})", error)
+        };
+        let db = ctx.get_db().await;
+        db.disable_highlights(user_id, &reason).await?;
+    }
+    Ok(())
+}
+
+async fn send_dm(
+    ctx: &client::Context,
+    notification: &HighlightNotification,
+) -> serenity::Result<()> {
+    let dm_channel = notification.user_id.create_dm_channel(&ctx).await?;
+    dm_channel.send_message(&ctx, |m| m.set_embed(notification.embed.clone())).await?;
+    Ok(())
+}
+
+fn is_forbidden(err: &serenity::Error) -> bool {
+    match err {
+        serenity::Error::Http(err) => err.status_code() == Some(StatusCode::FORBIDDEN),
+        _ => false,
+    }
+}
@@ -9,12 +9,17 @@ use std::sync::Arc;
 use tracing::Level;
 
 pub mod attachment_logging;
+pub mod auto_slowmode;
 mod error_handling;
 pub mod events;
+pub mod highlight_delivery;
 mod logging;
 
 use crate::logging::*;
 
+/// Prefix of text commands. Messages starting with it are ignored by most of the event handlers.
+pub const PREFIX: &str = "!";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let honeycomb_api_key = std::env::var("HONEYCOMB_API_KEY").ok();
@@ -55,7 +60,7 @@ async fn main() -> anyhow::Result<()> {
             })
         }),
         prefix_options: poise::PrefixFrameworkOptions {
-            prefix: Some("!".into()),
+            prefix: Some(PREFIX.into()),
             edit_tracker: Some(poise::EditTracker::for_timespan(std::time::Duration::from_secs(
                 10,
             ))),
@@ -0,0 +1,115 @@
+use robbb_db::auto_responder::{compile_auto_responder_pattern, AutoResponder};
+use robbb_util::embeds;
+
+use super::tag::tag_autocomplete_existing;
+use super::*;
+
+/// Cooldown used when none is given when adding an auto-responder.
+const DEFAULT_AUTO_RESPONDER_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Manage rules that automatically answer frequently asked questions with a tag.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("autorespond_add", "autorespond_remove", "autorespond_list")
+)]
+pub async fn autorespond(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Answer messages matching a regex with a tag
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "add"
+)]
+pub async fn autorespond_add(
+    ctx: Ctx<'_>,
+    #[description = "Case-insensitive regex the message has to match"] pattern: String,
+    #[description = "The tag to respond with"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag: String,
+    #[description = "Only respond in these channels or categories (separated by spaces)"]
+    channels: Option<String>,
+    #[description = "Minimum time between responses per channel, i.e.: 10m. Defaults to 10m"]
+    cooldown: Option<humantime::Duration>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let channels = parse_channel_list(channels.as_deref())?;
+    let tag = db.get_tag(&tag).await?.user_error("No tag with this name exists")?;
+    let cooldown = cooldown.map(|x| x.into()).unwrap_or(DEFAULT_AUTO_RESPONDER_COOLDOWN);
+
+    if let Err(err) = compile_auto_responder_pattern(&pattern) {
+        abort_with!(UserErr::Other(format!("{:#}", err)));
+    }
+
+    let responder =
+        db.add_auto_responder(&pattern, &tag.name, channels, cooldown, ctx.author().id).await?;
+    ctx.say_success(format!("Added auto-responder {}", format_auto_responder(&responder))).await?;
+    Ok(())
+}
+
+/// Remove an auto-responder
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove"
+)]
+pub async fn autorespond_remove(
+    ctx: Ctx<'_>,
+    #[description = "Id of the auto-responder, as shown in /autorespond list"] id: i64,
+) -> Res<()> {
+    let db = ctx.get_db();
+    if !db.remove_auto_responder(id).await? {
+        abort_with!("There is no auto-responder with that id");
+    }
+    ctx.say_success(format!("Removed auto-responder #{}", id)).await?;
+    Ok(())
+}
+
+/// List all auto-responders
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "list"
+)]
+pub async fn autorespond_list(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let responders = db.get_auto_responders().await?;
+    if responders.is_empty() {
+        abort_with!("There are no auto-responders");
+    }
+    let tag_names = db.list_tag_names().await?;
+
+    let fields = responders.iter().map(|responder| {
+        let tag_exists = tag_names.tags.iter().any(|x| x.eq_ignore_ascii_case(&responder.tag));
+        let mut value = format_auto_responder(responder);
+        if !tag_exists {
+            value.push_str("\n**The tag no longer exists, so this never responds**");
+        }
+        (format!("#{}", responder.id), value)
+    });
+    embeds::PaginatedEmbed::create_from_fields(
+        "Auto-responders".to_string(),
+        fields,
+        embeds::make_create_embed(ctx.discord(), |e| e).await,
+    )
+    .await
+    .reply_to(ctx, false)
+    .await?;
+    Ok(())
+}
+
+fn format_auto_responder(responder: &AutoResponder) -> String {
+    let mut description = format!("`{}` → {}", responder.pattern, responder.tag);
+    if !responder.channels.is_empty() {
+        let channels = responder.channels.iter().map(|x| x.mention()).join(", ");
+        description.push_str(&format!(", only in {}", channels));
+    }
+    description.push_str(&format!(", cooldown {}", humantime::Duration::from(responder.cooldown)));
+    description
+}
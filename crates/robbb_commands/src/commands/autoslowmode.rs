@@ -0,0 +1,107 @@
+use poise::serenity_prelude::Channel;
+
+use super::*;
+
+/// Configure automatic slowmode, which kicks in when the message rate of a channel spikes.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("autoslowmode_enable", "autoslowmode_disable", "autoslowmode_list")
+)]
+pub async fn autoslowmode(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Enable automatic slowmode in a channel, or change its settings.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "enable"
+)]
+pub async fn autoslowmode_enable(
+    ctx: Ctx<'_>,
+    #[min = 1]
+    #[description = "Messages per minute above which slowmode gets applied"]
+    threshold: u32,
+    #[description = "Maximum slowmode to apply (default: 1m)"] max_slowmode: Option<
+        humantime::Duration,
+    >,
+    #[description = "Channel (defaults to the current channel)"] channel: Option<Channel>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let channel_id = channel.map(|x| x.id()).unwrap_or_else(|| ctx.channel_id());
+    let max_slowmode = max_slowmode.map(|x| x.as_secs()).unwrap_or(60);
+    if max_slowmode < 2 || max_slowmode > 6 * 60 * 60 {
+        abort_with!("The maximum slowmode must be between 2 seconds and 6 hours");
+    }
+
+    db.set_auto_slowmode(channel_id, threshold, max_slowmode, ctx.author().id).await?;
+    ctx.say_success_mod_action(format!(
+        "Automatic slowmode is now enabled in {}, kicking in at {} messages per minute, up to {}s",
+        channel_id.mention(),
+        threshold,
+        max_slowmode
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Disable automatic slowmode in a channel.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "disable"
+)]
+pub async fn autoslowmode_disable(
+    ctx: Ctx<'_>,
+    #[description = "Channel (defaults to the current channel)"] channel: Option<Channel>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let channel_id = channel.map(|x| x.id()).unwrap_or_else(|| ctx.channel_id());
+    if !db.remove_auto_slowmode(channel_id).await? {
+        abort_with!("Automatic slowmode is not enabled in that channel");
+    }
+    ctx.say_success_mod_action(format!(
+        "Automatic slowmode is now disabled in {}",
+        channel_id.mention()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// List all channels with automatic slowmode enabled.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "list"
+)]
+pub async fn autoslowmode_list(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let settings = db.get_auto_slowmodes().await?;
+    if settings.is_empty() {
+        abort_with!("Automatic slowmode is not enabled in any channel");
+    }
+    ctx.send_embed_full(true, |e| {
+        e.title("Automatic slowmode");
+        e.description(
+            settings
+                .values()
+                .sorted_by_key(|x| x.channel_id)
+                .map(|x| {
+                    format!(
+                        "{}: {} messages per minute, up to {}s",
+                        x.channel_id.mention(),
+                        x.threshold,
+                        x.max_slowmode
+                    )
+                })
+                .join("\n"),
+        );
+    })
+    .await?;
+    Ok(())
+}
@@ -21,6 +21,10 @@ pub async fn emojistats(
     #[rename = "ascending"]
     sort_ascending: bool,
     #[description = "The emote you want statistics on"] emote: Option<String>,
+    #[description = "Also show the results as a chart"] chart: Option<ChartKind>,
+    #[description = "Only show the chart, without the text"]
+    #[flag]
+    chart_only: bool,
 ) -> Res<()> {
     let db = ctx.get_db();
     let ordering = match sort_ascending {
@@ -41,24 +45,47 @@ pub async fn emojistats(
             let emoji = guild_emojis
                 .get(&emoji_data.emoji.id)
                 .user_error("Could not find emoji in guild")?;
-            ctx.send_embed(|e| {
-                e.title(format!("Emoji usage for *{}*", emoji.name));
-                e.thumbnail(emoji.url());
-                e.description(format!(
-                    "**Reactions:** {} \n**In Text:** {} \n**Total:** {}",
-                    emoji_data.reactions,
-                    emoji_data.in_text,
-                    emoji_data.reactions + emoji_data.in_text
-                ));
-            })
-            .await?;
+            let text = format!(
+                "**Reactions:** {} \n**In Text:** {} \n**Total:** {}",
+                emoji_data.reactions,
+                emoji_data.in_text,
+                emoji_data.reactions + emoji_data.in_text
+            );
+            if chart.is_some() {
+                let chart_data = vec![
+                    ("Reactions".to_string(), emoji_data.reactions as f64),
+                    ("In text".to_string(), emoji_data.in_text as f64),
+                ];
+                let title = format!("Emoji usage for {}", emoji.name);
+                send_stats_embed(ctx, title, text, chart, chart_only, "", chart_data).await?;
+            } else {
+                ctx.send_embed(|e| {
+                    e.title(format!("Emoji usage for *{}*", emoji.name));
+                    e.thumbnail(emoji.url());
+                    e.description(text);
+                })
+                .await?;
+            }
         }
         None => {
             let emojis = db.get_top_emoji_stats(10, ordering).await?;
-            ctx.send_embed(|e| {
-                e.title("Emoji usage");
-                e.description(display_emoji_list(&guild_emojis, emojis.into_iter()));
-            })
+            let chart_data = emojis
+                .iter()
+                .filter_map(|emoji| {
+                    let guild_emoji = guild_emojis.get(&emoji.emoji.id)?;
+                    Some((guild_emoji.name.clone(), (emoji.reactions + emoji.in_text) as f64))
+                })
+                .collect_vec();
+            let text = display_emoji_list(&guild_emojis, emojis.into_iter());
+            send_stats_embed(
+                ctx,
+                "Emoji usage".to_string(),
+                text,
+                chart,
+                chart_only,
+                "",
+                chart_data,
+            )
             .await?;
         }
     }
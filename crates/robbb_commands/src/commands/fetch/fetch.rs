@@ -1,6 +1,10 @@
 use std::collections::HashMap;
 
-use robbb_db::{fetch::Fetch, fetch_field::FetchField};
+use robbb_db::{
+    fetch::{Fetch, MAX_FETCH_HISTORY_PER_USER},
+    fetch_field::{FetchField, FETCH_KEY_ORDER},
+};
+use robbb_util::embeds;
 
 use super::*;
 
@@ -16,11 +20,12 @@ pub async fn fetch(
     ctx.defer().await?;
 
     // Query the database
-    let fetch_info: Fetch = db.get_fetch(user.user.id).await?.unwrap_or_else(|| Fetch {
+    let mut fetch_info: Fetch = db.get_fetch(user.user.id).await?.unwrap_or_else(|| Fetch {
         user: user.user.id,
         info: HashMap::new(),
         create_date: None,
     });
+    db.get_fetch_value_aliases().await?.canonicalize_fetch(&mut fetch_info.info);
 
     let create_date = fetch_info.create_date;
     let fetch_data: Vec<(FetchField, String)> = fetch_info.get_values_ordered();
@@ -81,3 +86,91 @@ pub async fn fetch(
 
     Ok(())
 }
+
+/// See how a users setup changed over time.
+#[poise::command(slash_command, guild_only, prefix_command, rename = "fetchhistory")]
+pub async fn fetch_history(
+    ctx: Ctx<'_>,
+    #[description = "The user"] user: Option<Member>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let user = member_or_self(ctx, user).await?;
+    let history = db.get_fetch_history(user.user.id).await?;
+    if history.is_empty() {
+        abort_with!("This user hasn't set their fetch yet");
+    }
+
+    let empty_fetch = HashMap::new();
+    let fields = history
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, snapshot)| {
+            let previous = idx.checked_sub(1).map_or(&empty_fetch, |x| &history[x].info);
+            let changes = format_fetch_changes(previous, &snapshot.info)?;
+            let date = snapshot.create_date?;
+            Some((
+                date.format("%Y-%m-%d").to_string(),
+                format!("{}\n{}", util::format_date_ago(date), changes),
+            ))
+        })
+        .rev()
+        .collect_vec();
+    if fields.is_empty() {
+        abort_with!("This user hasn't changed their fetch yet");
+    }
+
+    let base_embed = embeds::make_create_embed(ctx.discord(), |e| {
+        e.author_user(&user.user);
+        e.color_opt(user.colour(&ctx.discord()));
+        e.description(format!(
+            "Changes to the fetch of {}. Only the last {} updates are kept.",
+            user.user.mention(),
+            MAX_FETCH_HISTORY_PER_USER
+        ))
+    })
+    .await;
+    embeds::PaginatedEmbed::create_from_fields("Fetch history".to_string(), fields, base_embed)
+        .await
+        .reply_to(ctx, false)
+        .await?;
+    Ok(())
+}
+
+/// Describe the changes between two fetches, one line per changed field.
+/// Returns `None` if nothing visible changed.
+fn format_fetch_changes(
+    old: &HashMap<FetchField, String>,
+    new: &HashMap<FetchField, String>,
+) -> Option<String> {
+    let format_value = |field: &FetchField, info: &HashMap<FetchField, String>| {
+        let value = info.get(field)?.clone();
+        let value = format_fetch_field_value(field, value)?;
+        Some(util::ellipsis_text(&value, 100))
+    };
+    let changes = FETCH_KEY_ORDER
+        .iter()
+        .filter_map(|field| {
+            let old_value = format_value(field, old);
+            let new_value = format_value(field, new);
+            if old_value == new_value {
+                return None;
+            }
+            match (old_value, new_value) {
+                (_, Some(_)) if *field == FetchField::Image => {
+                    Some(format!("**{}**: new image", field))
+                }
+                (Some(old_value), Some(new_value)) => {
+                    Some(format!("**{}**: {} → {}", field, old_value, new_value))
+                }
+                (None, Some(new_value)) => Some(format!("**{}**: {}", field, new_value)),
+                (Some(_), None) => Some(format!("**{}**: removed", field)),
+                (None, None) => None,
+            }
+        })
+        .join("\n");
+    if changes.is_empty() {
+        None
+    } else {
+        Some(util::ellipsis_text(&changes, 1024))
+    }
+}
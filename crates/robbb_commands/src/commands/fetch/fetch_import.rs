@@ -0,0 +1,315 @@
+//! Parsing fetch data from the JSON output of fastfetch and `fetcher.sh --json`.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use robbb_db::fetch_field::FetchField;
+use serde_json::Value;
+
+/// Keys of fastfetch results that describe a program, best first.
+const PROCESS_NAME_KEYS: &[&str] = &["prettyName", "name", "processName"];
+
+/// Maximum length of a single imported value.
+const MAX_VALUE_LENGTH: usize = 200;
+
+/// Fetch values parsed from an imported file.
+#[derive(Debug, Default)]
+pub struct ImportedFetch {
+    pub values: HashMap<FetchField, String>,
+    /// Keys or fastfetch modules that don't correspond to any fetch field.
+    pub ignored: Vec<String>,
+}
+
+/// Parse either fastfetch's JSON output, which is a list of modules,
+/// or the flat object of field names to values that `fetcher.sh --json` prints.
+pub fn parse_fetch_json(text: &str) -> Result<ImportedFetch, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let mut imported = match json {
+        Value::Array(modules) => parse_fastfetch(&modules),
+        Value::Object(values) => parse_flat(&values),
+        _ => return Err("Expected a JSON object or fastfetch's list of modules".to_string()),
+    };
+
+    if let Some(memory) = imported.values.remove(&FetchField::Memory) {
+        let bytes = byte_unit::Byte::from_str(&memory)
+            .map_err(|_| format!("Malformed value provided for Memory: {}", memory))?;
+        imported.values.insert(FetchField::Memory, bytes.get_bytes().to_string());
+    }
+    // images have to go through the attachment dump, so they're only set through /setfetch update
+    if imported.values.remove(&FetchField::Image).is_some() {
+        imported.ignored.push(FetchField::Image.to_string());
+    }
+
+    imported.values = imported
+        .values
+        .into_iter()
+        .map(|(field, value)| (field, value.trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(field, value)| (field, robbb_util::util::ellipsis_text(&value, MAX_VALUE_LENGTH)))
+        .collect();
+    if imported.values.is_empty() {
+        return Err("Couldn't find any fetch values in that file".to_string());
+    }
+    Ok(imported)
+}
+
+fn parse_flat(values: &serde_json::Map<String, Value>) -> ImportedFetch {
+    let mut imported = ImportedFetch::default();
+    for (key, value) in values {
+        let field = FetchField::from_str(key)
+            .or_else(|_| FetchField::from_str(&key.replace('_', " ")))
+            .ok();
+        match (field, value_to_string(value)) {
+            (Some(field), Some(value)) => {
+                imported.values.insert(field, value);
+            }
+            _ => imported.ignored.push(key.clone()),
+        }
+    }
+    imported
+}
+
+fn parse_fastfetch(modules: &[Value]) -> ImportedFetch {
+    let mut imported = ImportedFetch::default();
+    let mut de = None;
+    let mut wm = None;
+    for module in modules {
+        let kind = module.get("type").and_then(|x| x.as_str()).unwrap_or_default();
+        // modules that failed to detect anything contain an error instead of a result
+        let result = match module.get("result") {
+            Some(result) if !result.is_null() => result,
+            _ => continue,
+        };
+
+        let (field, value) = match kind {
+            "OS" => (FetchField::Distro, string_at(result, &["name"])),
+            "Kernel" => {
+                let parts = [string_at(result, &["name"]), string_at(result, &["release"])];
+                (FetchField::Kernel, Some(parts.into_iter().flatten().join(" ")))
+            }
+            "Terminal" => (FetchField::Terminal, string_at(result, PROCESS_NAME_KEYS)),
+            "Shell" => (FetchField::Shell, string_at(result, PROCESS_NAME_KEYS)),
+            "Editor" => (FetchField::Editor, string_at(result, PROCESS_NAME_KEYS)),
+            "DE" => {
+                de = string_at(result, PROCESS_NAME_KEYS);
+                continue;
+            }
+            "WM" => {
+                wm = string_at(result, PROCESS_NAME_KEYS);
+                (FetchField::DisplayProtocol, string_at(result, &["protocolName"]))
+            }
+            "Display" => {
+                let displays = result.as_array().map(|x| x.as_slice()).unwrap_or_default();
+                let resolutions = displays.iter().filter_map(display_resolution).join(", ");
+                (FetchField::Resolution, Some(resolutions))
+            }
+            "Theme" => {
+                let theme = string_at(result, &["gtk3", "theme1"]).or_else(|| first_string(result));
+                (FetchField::GTK3, theme)
+            }
+            "Icons" => {
+                let icons = string_at(result, &["gtk3", "icons1"]).or_else(|| first_string(result));
+                (FetchField::Icons, icons)
+            }
+            "CPU" => (FetchField::CPU, string_at(result, &["cpu", "name"])),
+            "GPU" => {
+                let gpus = result.as_array().map(|x| x.as_slice()).unwrap_or_default();
+                let names = gpus.iter().filter_map(|gpu| {
+                    let name = string_at(gpu, &["name"])?;
+                    match string_at(gpu, &["vendor"]) {
+                        Some(vendor) if !name.starts_with(&vendor) => {
+                            Some(format!("{} {}", vendor, name))
+                        }
+                        _ => Some(name),
+                    }
+                });
+                (FetchField::GPU, Some(names.collect_vec().join(", ")))
+            }
+            "Memory" => (FetchField::Memory, result.get("total").and_then(value_to_string)),
+            _ => {
+                imported.ignored.push(kind.to_string());
+                continue;
+            }
+        };
+        if let Some(value) = value {
+            imported.values.insert(field, value);
+        }
+    }
+
+    let de_wm = match (de, wm) {
+        (Some(de), Some(wm)) if !de.eq_ignore_ascii_case(&wm) => Some(format!("{} ({})", de, wm)),
+        (de, wm) => de.or(wm),
+    };
+    if let Some(de_wm) = de_wm {
+        imported.values.insert(FetchField::DEWM, de_wm);
+    }
+    imported
+}
+
+/// Get the resolution of a display, which newer versions of fastfetch nest within `output`.
+fn display_resolution(display: &Value) -> Option<String> {
+    let sizes = display.get("output").unwrap_or(display);
+    let width = sizes.get("width")?.as_u64()?;
+    let height = sizes.get("height")?.as_u64()?;
+    Some(format!("{}x{}", width, height))
+}
+
+/// Get the first of the given keys that contains a non-empty string or number.
+fn string_at(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| value.get(key).and_then(value_to_string))
+}
+
+fn first_string(value: &Value) -> Option<String> {
+    value.as_object()?.values().find_map(value_to_string)
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imported_values(text: &str) -> HashMap<FetchField, String> {
+        parse_fetch_json(text).unwrap().values
+    }
+
+    #[test]
+    fn test_parse_fastfetch() {
+        let text = r#"[
+            {"type": "Title", "result": {"userName": "user", "hostName": "host"}},
+            {"type": "OS", "result": {
+                "name": "Fedora Linux",
+                "prettyName": "Fedora Linux 39 (Workstation Edition)",
+                "id": "fedora",
+                "version": "39 (Workstation Edition)",
+                "versionID": "39"
+            }},
+            {"type": "Kernel", "result": {"name": "Linux", "release": "6.5.6-300.fc39.x86_64"}},
+            {"type": "Shell", "result": {"processName": "zsh", "prettyName": "zsh", "version": "5.9"}},
+            {"type": "Terminal", "result": {"processName": "kitty", "prettyName": "kitty"}},
+            {"type": "DE", "result": {"processName": "gnome-shell", "prettyName": "GNOME"}},
+            {"type": "WM", "result": {"processName": "gnome-shell", "prettyName": "Mutter", "protocolName": "Wayland"}},
+            {"type": "CPU", "result": {"cpu": "AMD Ryzen 7 5800X (16) @ 4.85 GHz", "vendor": "AMD"}},
+            {"type": "GPU", "result": [
+                {"vendor": "AMD", "name": "Radeon RX 6700 XT"},
+                {"vendor": "Intel", "name": "Intel UHD Graphics 630"}
+            ]},
+            {"type": "Memory", "result": {"total": 16777216000, "used": 4294967296}},
+            {"type": "Battery", "error": "No batteries found"}
+        ]"#;
+        let values = imported_values(text);
+        assert_eq!(values[&FetchField::Distro], "Fedora Linux");
+        assert_eq!(values[&FetchField::Kernel], "Linux 6.5.6-300.fc39.x86_64");
+        assert_eq!(values[&FetchField::Shell], "zsh");
+        assert_eq!(values[&FetchField::Terminal], "kitty");
+        assert_eq!(values[&FetchField::DEWM], "GNOME (Mutter)");
+        assert_eq!(values[&FetchField::DisplayProtocol], "Wayland");
+        assert_eq!(values[&FetchField::CPU], "AMD Ryzen 7 5800X (16) @ 4.85 GHz");
+        assert_eq!(values[&FetchField::GPU], "AMD Radeon RX 6700 XT, Intel UHD Graphics 630");
+        assert_eq!(values[&FetchField::Memory], "16777216000");
+        assert_eq!(values.len(), 9);
+    }
+
+    #[test]
+    fn test_parse_fastfetch_ignored_modules() {
+        let text = r#"[
+            {"type": "OS", "result": {"name": "Arch Linux", "prettyName": "Arch Linux"}},
+            {"type": "Uptime", "result": {"uptime": 12345}},
+            {"type": "Battery", "error": "No batteries found"}
+        ]"#;
+        let imported = parse_fetch_json(text).unwrap();
+        assert_eq!(imported.values[&FetchField::Distro], "Arch Linux");
+        // modules without a result are skipped entirely
+        assert_eq!(imported.ignored, vec!["Uptime".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_fastfetch_wm_only() {
+        let text = r#"[
+            {"type": "WM", "result": {"processName": "sway", "prettyName": "Sway", "protocolName": "Wayland"}}
+        ]"#;
+        assert_eq!(imported_values(text)[&FetchField::DEWM], "Sway");
+    }
+
+    #[test]
+    fn test_parse_fastfetch_display_old_layout() {
+        let text = r#"[
+            {"type": "Display", "result": [
+                {"width": 2560, "height": 1440, "refreshRate": 144, "name": "DP-1"},
+                {"width": 1920, "height": 1080, "refreshRate": 60, "name": "HDMI-A-1"}
+            ]}
+        ]"#;
+        assert_eq!(imported_values(text)[&FetchField::Resolution], "2560x1440, 1920x1080");
+    }
+
+    #[test]
+    fn test_parse_fastfetch_display_new_layout() {
+        let text = r#"[
+            {"type": "Display", "result": [
+                {
+                    "id": 1,
+                    "name": "DP-1",
+                    "output": {"width": 3840, "height": 2160, "refreshRate": 60},
+                    "scaled": {"width": 1920, "height": 1080},
+                    "primary": true
+                }
+            ]}
+        ]"#;
+        assert_eq!(imported_values(text)[&FetchField::Resolution], "3840x2160");
+    }
+
+    #[test]
+    fn test_parse_fetcher_json() {
+        let text = r#"{
+	"distro": "Debian GNU/Linux 12",
+	"kernel": "6.1.0-13-amd64",
+	"terminal": "alacritty",
+	"editor": "nvim",
+	"shell": "bash",
+	"de_wm": "i3",
+	"display_protocol": "x11",
+	"gtk3_theme": "Adwaita-dark",
+	"gtk_icon_theme": "Papirus",
+	"cpu": "Intel(R) Core(TM) i5-8250U CPU @ 1.60GHz",
+	"memory": "16303820 kB"
+}"#;
+        let values = imported_values(text);
+        assert_eq!(values[&FetchField::Distro], "Debian GNU/Linux 12");
+        assert_eq!(values[&FetchField::Kernel], "6.1.0-13-amd64");
+        assert_eq!(values[&FetchField::Terminal], "alacritty");
+        assert_eq!(values[&FetchField::Editor], "nvim");
+        assert_eq!(values[&FetchField::Shell], "bash");
+        assert_eq!(values[&FetchField::DEWM], "i3");
+        assert_eq!(values[&FetchField::DisplayProtocol], "x11");
+        assert_eq!(values[&FetchField::GTK3], "Adwaita-dark");
+        assert_eq!(values[&FetchField::Icons], "Papirus");
+        assert_eq!(values[&FetchField::CPU], "Intel(R) Core(TM) i5-8250U CPU @ 1.60GHz");
+        assert_eq!(values[&FetchField::Memory], "16303820000");
+        assert_eq!(values.len(), 11);
+    }
+
+    #[test]
+    fn test_parse_flat_ignores_unknown_keys_and_images() {
+        let text = r#"{"Distro": "NixOS", "DE/WM": "Hyprland", "uptime": "3 days", "image": "https://example.com/a.png"}"#;
+        let imported = parse_fetch_json(text).unwrap();
+        assert_eq!(imported.values[&FetchField::Distro], "NixOS");
+        assert_eq!(imported.values[&FetchField::DEWM], "Hyprland");
+        assert_eq!(imported.values.len(), 2);
+        assert_eq!(imported.ignored, vec!["uptime".to_string(), "Image".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_fetch_json_errors() {
+        assert!(parse_fetch_json("not json").is_err());
+        assert!(parse_fetch_json(r#""distro""#).is_err());
+        assert!(parse_fetch_json(r#"{"uptime": "3 days"}"#).is_err());
+        assert!(parse_fetch_json(r#"{"memory": "a lot"}"#).is_err());
+    }
+}
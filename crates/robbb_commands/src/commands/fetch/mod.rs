@@ -1,6 +1,7 @@
 pub use super::*;
 #[allow(clippy::module_inception)]
 pub mod fetch;
+pub mod fetch_import;
 use robbb_db::fetch_field::FetchField;
 
 pub use fetch::*;
@@ -2,8 +2,15 @@ use anyhow::Context;
 use chrono::Utc;
 use poise::serenity_prelude::{Attachment, AttachmentType, ChannelId, Http};
 
+use robbb_db::fetch_field::FETCH_KEY_ORDER;
+use robbb_util::embeds;
+
+use super::fetch_import;
 use super::*;
-use std::{borrow::Cow, collections::HashMap};
+use std::borrow::Cow;
+
+/// Maximum size of a file imported with `/setfetch import`.
+const MAX_FETCH_IMPORT_SIZE: u64 = 1024 * 1024;
 
 const SETFETCH_USAGE: &str = indoc::indoc!("
     Run this: 
@@ -11,6 +18,9 @@ const SETFETCH_USAGE: &str = indoc::indoc!("
     and follow the instructions. It's recommended that you download and read the script before running it, 
     as piping curl to sh isn't always the safest practice. (<https://blog.dijit.sh/don-t-pipe-curl-to-bash>) 
 
+    Alternatively, run `curl -s https://raw.githubusercontent.com/unixporn/robbb/master/fetcher.sh | sh -s -- --json > fetch.json` \
+    or `fastfetch --format json > fetch.json` and upload the file with `/setfetch import`.
+
     **NOTE**: use `/setfetch update` to manually update your fetch (including the image!).
     **NOTE**: /git, /dotfiles, and /description are different commands"
 );
@@ -20,7 +30,7 @@ const SETFETCH_USAGE: &str = indoc::indoc!("
     slash_command,
     guild_only,
     rename = "setfetch",
-    subcommands("set_fetch_script", "set_fetch_update", "set_fetch_clear")
+    subcommands("set_fetch_script", "set_fetch_update", "set_fetch_import", "set_fetch_clear")
 )]
 pub async fn set_fetch(_ctx: Ctx<'_>) -> Res<()> {
     Ok(())
@@ -106,12 +116,57 @@ pub async fn set_fetch_update(
     };
     let info = data.into_iter().filter_map(|(k, v)| Some((k, v?))).collect();
     let db = ctx.get_db();
-    db.update_fetch(ctx.author().id, info).await?;
+    db.update_fetch(ctx.author().id, info, config.fetch_canonicalize_on_write).await?;
     ctx.say_success("Successfully updated your fetch data!").await?;
 
     Ok(())
 }
 
+/// Import your fetch data from the JSON output of fastfetch or our fetch script
+#[poise::command(slash_command, guild_only, rename = "import")]
+pub async fn set_fetch_import(
+    ctx: Ctx<'_>,
+    #[description = "Output of `fastfetch --format json` or `fetcher.sh --json`"] file: Attachment,
+) -> Res<()> {
+    if file.size > MAX_FETCH_IMPORT_SIZE {
+        abort_with!("That file is too large");
+    }
+    ctx.defer().await?;
+
+    let data = file.download().await?;
+    let text = String::from_utf8(data).user_error("That file isn't a text file")?;
+    let imported = match fetch_import::parse_fetch_json(&text) {
+        Ok(imported) => imported,
+        Err(err) => abort_with!(UserErr::Other(format!("Couldn't import that file: {}", err))),
+    };
+
+    let preview = embeds::make_create_embed(ctx.discord(), |e| {
+        e.title("Update your fetch with these values?");
+        for field in FETCH_KEY_ORDER {
+            let value = imported.values.get(field).cloned().unwrap_or_default();
+            if let Some(value) = format_fetch_field_value(field, value) {
+                e.field(field, value, true);
+            }
+        }
+        if !imported.ignored.is_empty() {
+            let ignored = util::ellipsis_text(&imported.ignored.join(", "), 1024);
+            e.field("Ignored", ignored, false);
+        }
+        e
+    })
+    .await;
+    if !prompt_confirmation(ctx, preview).await? {
+        ctx.say_error("Cancelled the import").await?;
+        return Ok(());
+    }
+
+    let config = ctx.get_config();
+    let db = ctx.get_db();
+    db.update_fetch(ctx.author().id, imported.values, config.fetch_canonicalize_on_write).await?;
+    ctx.say_success("Successfully updated your fetch data!").await?;
+    Ok(())
+}
+
 /// Clear your fetch data
 #[poise::command(slash_command, guild_only, prefix_command, rename = "clear")]
 pub async fn set_fetch_clear(
@@ -128,8 +183,8 @@ pub async fn set_fetch_clear(
         }
         ctx.say_success(format!("Successfully cleared your {}", field)).await?;
     } else {
-        db.set_fetch(ctx.author().id, HashMap::new(), Some(Utc::now())).await?;
-        ctx.say_success("Successfully cleared your fetch data!").await?;
+        db.clear_fetch(ctx.author().id).await?;
+        ctx.say_success("Successfully cleared your fetch data and history!").await?;
     }
     Ok(())
 }
@@ -0,0 +1,83 @@
+use poise::serenity_prelude::Role;
+
+use super::*;
+
+/// Configure how many highlights members of a role may have.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("highlightlimit_set", "highlightlimit_remove", "highlightlimit_list")
+)]
+pub async fn highlightlimit(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Set the maximum amount of highlights for members of a role. Use @everyone for the default.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "set"
+)]
+pub async fn highlightlimit_set(
+    ctx: Ctx<'_>,
+    #[description = "The role"] role: Role,
+    #[min = 0]
+    #[max = 100]
+    #[description = "Maximum amount of highlights"]
+    limit: u32,
+) -> Res<()> {
+    let db = ctx.get_db();
+    db.set_highlight_limit(role.id, limit as usize).await?;
+    ctx.say_success(format!("Members of {} can now have up to {} highlights", role.name, limit))
+        .await?;
+    Ok(())
+}
+
+/// Remove the highlight limit of a role.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove"
+)]
+pub async fn highlightlimit_remove(
+    ctx: Ctx<'_>,
+    #[description = "The role"] role: Role,
+) -> Res<()> {
+    let db = ctx.get_db();
+    if !db.remove_highlight_limit(role.id).await? {
+        abort_with!("There is no highlight limit set for that role");
+    }
+    ctx.say_success(format!("Removed the highlight limit of {}", role.name)).await?;
+    Ok(())
+}
+
+/// List the configured highlight limits.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "list"
+)]
+pub async fn highlightlimit_list(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let limits = db.get_highlight_limits().await?;
+    if limits.is_empty() {
+        abort_with!("No highlight limits are configured, the defaults apply to everyone");
+    }
+    ctx.send_embed_full(true, |e| {
+        e.title("Highlight limits");
+        e.description(
+            limits
+                .iter()
+                .sorted_by_key(|(_, limit)| std::cmp::Reverse(**limit))
+                .map(|(role_id, limit)| format!("{}: {}", role_id.mention(), limit))
+                .join("\n"),
+        );
+        e.footer(|f| f.text("Members get the highest limit of any of their roles"));
+    })
+    .await?;
+    Ok(())
+}
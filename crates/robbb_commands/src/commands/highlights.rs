@@ -1,14 +1,27 @@
-use poise::serenity_prelude::CreateEmbed;
+use poise::serenity_prelude::{Channel, CreateEmbed, RoleId, User};
+use robbb_db::{highlight_digest::HighlightMode, highlights::Highlight};
 
 use super::*;
 use crate::checks::{self, PermissionLevel};
 
+/// Highlight limits used for roles without a configured limit.
+const DEFAULT_MOD_HIGHLIGHT_LIMIT: usize = 20;
+const DEFAULT_HIGHLIGHT_LIMIT: usize = 4;
+
 /// Get notified when someone mentions a word you care about.
 #[poise::command(
     slash_command,
     rename = "highlight",
     aliases("highlights", "hl"),
-    subcommands("highlights_add", "highlights_list", "highlights_clear", "highlights_remove",)
+    subcommands(
+        "highlights_add",
+        "highlights_list",
+        "highlights_clear",
+        "highlights_remove",
+        "highlights_ignore",
+        "highlights_mode",
+        "highlights_threads",
+    )
 )]
 pub async fn highlights(_: Ctx<'_>) -> Res<()> {
     Ok(())
@@ -18,17 +31,28 @@ pub async fn highlights(_: Ctx<'_>) -> Res<()> {
 #[poise::command(prefix_command, slash_command, guild_only, rename = "add")]
 pub async fn highlights_add(
     ctx: Ctx<'_>,
-    #[description = "The word you want to be notified about"] trigger: String,
+    #[description = "The word or phrase you want to be notified about"] trigger: String,
+    #[description = "Treat the trigger as a regular expression"] regex: Option<bool>,
+    #[description = "Only notify in these channels or categories"] channels: Option<String>,
+    #[description = "Never notify in these channels or categories"] exclude: Option<String>,
 ) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
+    let is_regex = regex.unwrap_or(false);
+    let trigger =
+        if is_regex { trigger.trim().to_string() } else { trigger.split_whitespace().join(" ") };
     if trigger.len() < 3 {
         abort_with!("Highlight has to be longer than 2 characters");
     }
+    if is_regex {
+        if let Err(err) = robbb_db::highlights::compile_regex_trigger(&trigger) {
+            abort_with!(UserErr::Other(format!("Can't use that regex: {}", err)));
+        }
+    }
+    let include_channels = parse_channel_list(channels.as_deref())?;
+    let exclude_channels = parse_channel_list(exclude.as_deref())?;
 
     let db = ctx.get_db();
-    let max_highlight_cnt = match checks::get_permission_level(ctx.discord(), ctx.author()).await? {
-        PermissionLevel::Mod => 20,
-        _ => 4,
-    };
+    let max_highlight_cnt = get_highlight_limit(ctx).await?;
 
     let highlights = db.get_highlights().await?;
     let highlights_by_user_cnt = highlights.triggers_for_user(ctx.author().id).count();
@@ -40,24 +64,20 @@ pub async fn highlights_add(
         )));
     }
 
-    ctx.author()
-        .id
-        .create_dm_channel(&ctx.discord())
-        .await
-        .user_error("Couldn't open a DM to you - do you have me blocked?")?
-        .send_message(&ctx.discord(), |m| {
-            m.embed(|e| {
-                e.title("Test to see if you can receive DMs");
-                e.description(format!(
-                    "If everything went ok, you'll be notified whenever someone says `{}`",
-                    trigger
-                ))
-            })
-        })
-        .await
-        .user_error("Couldn't send you a DM :/\nDo you allow DMs from server members?")?;
+    send_test_dm(
+        ctx,
+        format!("If everything went ok, you'll be notified whenever someone says `{}`", trigger),
+    )
+    .await?;
 
-    db.set_highlight(ctx.author().id, trigger.clone()).await.user_error(
+    let highlight = Highlight {
+        trigger: trigger.clone(),
+        user: ctx.author().id,
+        is_regex,
+        include_channels,
+        exclude_channels,
+    };
+    db.set_highlight(highlight).await.user_error(
         "Couldn't add highlight, something went wrong (highlight might already be present)",
     )?;
 
@@ -69,10 +89,14 @@ pub async fn highlights_add(
 /// List all of your highlights
 #[poise::command(prefix_command, slash_command, guild_only, rename = "list")]
 pub async fn highlights_list(ctx: Ctx<'_>) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
     let db = ctx.get_db();
     let highlights = db.get_highlights().await?;
 
-    let highlights_list = highlights.triggers_for_user(ctx.author().id).join("\n");
+    let highlights_list =
+        highlights.highlights_for_user(ctx.author().id).map(format_highlight).join("\n");
+    let ignores = db.get_highlight_ignores(ctx.author().id).await?;
+    let settings = db.get_highlight_settings(ctx.author().id).await?;
 
     if highlights_list.is_empty() {
         abort_with!("You don't seem to have set any highlights");
@@ -80,6 +104,26 @@ pub async fn highlights_list(ctx: Ctx<'_>) -> Res<()> {
         try_dm_or_ephemeral_response(ctx, |e| {
             e.title("Your highlights");
             e.description(highlights_list);
+            e.field("Mode", settings.mode, true);
+            e.field(
+                "Threads",
+                if settings.thread_highlights { "Enabled" } else { "Disabled" },
+                true,
+            );
+            if !ignores.users.is_empty() {
+                e.field(
+                    "Ignored users",
+                    ignores.users.iter().map(|x| x.mention()).join(", "),
+                    false,
+                );
+            }
+            if !ignores.channels.is_empty() {
+                e.field(
+                    "Ignored channels",
+                    ignores.channels.iter().map(|x| x.mention()).join(", "),
+                    false,
+                );
+            }
         })
         .await?;
     }
@@ -112,6 +156,185 @@ pub async fn highlights_clear(ctx: Ctx<'_>) -> Res<()> {
     Ok(())
 }
 
+/// Ignore highlights from a user or in a channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "ignore",
+    subcommands("highlights_ignore_user", "highlights_ignore_channel")
+)]
+pub async fn highlights_ignore(_: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Toggle ignoring highlights triggered by a user
+#[poise::command(prefix_command, slash_command, guild_only, rename = "user")]
+pub async fn highlights_ignore_user(
+    ctx: Ctx<'_>,
+    #[description = "The user whose messages shouldn't notify you"] user: User,
+) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
+    let db = ctx.get_db();
+    if db.add_highlight_ignored_user(ctx.author().id, user.id).await? {
+        ctx.say_success(format!("You will no longer be notified about messages by {}", user.tag()))
+            .await?;
+    } else {
+        db.remove_highlight_ignored_user(ctx.author().id, user.id).await?;
+        ctx.say_success(format!("You will be notified about messages by {} again", user.tag()))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Toggle ignoring highlights in a channel or category
+#[poise::command(prefix_command, slash_command, guild_only, rename = "channel")]
+pub async fn highlights_ignore_channel(
+    ctx: Ctx<'_>,
+    #[description = "The channel or category you don't want to be notified about"] channel: Channel,
+) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
+    let db = ctx.get_db();
+    if db.add_highlight_ignored_channel(ctx.author().id, channel.id()).await? {
+        ctx.say_success(format!(
+            "You will no longer be notified about messages in {}",
+            channel.mention()
+        ))
+        .await?;
+    } else {
+        db.remove_highlight_ignored_channel(ctx.author().id, channel.id()).await?;
+        ctx.say_success(format!(
+            "You will be notified about messages in {} again",
+            channel.mention()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Choose whether to get notified immediately or in an hourly or daily digest
+#[poise::command(prefix_command, slash_command, guild_only, rename = "mode")]
+pub async fn highlights_mode(
+    ctx: Ctx<'_>,
+    #[description = "How you want to be notified"] mode: HighlightMode,
+) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
+    let db = ctx.get_db();
+    db.set_highlight_mode(ctx.author().id, mode).await?;
+    let message = match mode {
+        HighlightMode::Immediate => {
+            "You will be notified about each highlight right away".to_string()
+        }
+        _ => format!("You will get your highlights as a {}", mode.to_string().to_lowercase()),
+    };
+    ctx.say_success(message).await?;
+    Ok(())
+}
+
+/// Choose whether you want to get highlighted for messages in threads
+#[poise::command(prefix_command, slash_command, guild_only, rename = "threads")]
+pub async fn highlights_threads(
+    ctx: Ctx<'_>,
+    #[description = "Get highlighted in threads and forum posts"] enabled: bool,
+) -> Res<()> {
+    check_disabled_highlights(ctx).await?;
+    let db = ctx.get_db();
+    db.set_thread_highlights(ctx.author().id, enabled).await?;
+    if enabled {
+        ctx.say_success("You will be notified about highlights in threads").await?;
+    } else {
+        ctx.say_success("You won't be notified about highlights in threads anymore").await?;
+    }
+    Ok(())
+}
+
+/// Get the maximum amount of highlights the user may have. This is the highest limit
+/// configured for any of their roles, including @everyone, falling back to the defaults.
+async fn get_highlight_limit(ctx: Ctx<'_>) -> Res<usize> {
+    let (config, db) = (ctx.get_config(), ctx.get_db());
+    let limits = db.get_highlight_limits().await?;
+    let member = ctx.author_member().await.user_error("Failed to fetch your roles")?;
+    // the @everyone role has the same id as the guild
+    let everyone_role = RoleId(member.guild_id.0);
+    let configured_limit = member
+        .roles
+        .iter()
+        .chain(std::iter::once(&everyone_role))
+        .filter_map(|x| limits.get(x))
+        .max()
+        .copied();
+    // mods keep their default, unless a limit is configured for the mod role itself
+    let is_mod =
+        checks::get_permission_level(ctx.discord(), ctx.author()).await? == PermissionLevel::Mod;
+    let mod_limit =
+        (is_mod && !limits.contains_key(&config.role_mod)).then(|| DEFAULT_MOD_HIGHLIGHT_LIMIT);
+    Ok(configured_limit.into_iter().chain(mod_limit).max().unwrap_or(DEFAULT_HIGHLIGHT_LIMIT))
+}
+
+/// If the user's highlights got disabled because we couldn't DM them, tell them why
+/// and abort the command. Once we can DM them again, their highlights get re-enabled.
+/// Removing highlights doesn't go through this, so it works even with closed DMs.
+async fn check_disabled_highlights(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let reason = match db.get_highlights_disabled_reason(ctx.author().id).await? {
+        Some(reason) => reason,
+        None => return Ok(()),
+    };
+    let dm_works = send_test_dm(
+        ctx,
+        "Your highlights have been re-enabled, you'll get notifications again.".to_string(),
+    )
+    .await
+    .is_ok();
+    if dm_works {
+        db.enable_highlights(ctx.author().id).await?;
+        ctx.say_success(format!(
+            "Your highlights had been disabled, as {}. I can DM you again, so they're enabled again.",
+            reason
+        ))
+        .await?;
+    } else {
+        abort_with!(UserErr::Other(format!(
+            "Your highlights are disabled, as {}. Allow DMs from server members and use `/highlight` again to re-enable them.",
+            reason
+        )));
+    }
+    Ok(())
+}
+
+async fn send_test_dm(ctx: Ctx<'_>, description: String) -> Res<()> {
+    ctx.author()
+        .id
+        .create_dm_channel(&ctx.discord())
+        .await
+        .user_error("Couldn't open a DM to you - do you have me blocked?")?
+        .send_message(&ctx.discord(), |m| {
+            m.embed(|e| {
+                e.title("Test to see if you can receive DMs");
+                e.description(description)
+            })
+        })
+        .await
+        .user_error("Couldn't send you a DM :/\nDo you allow DMs from server members?")?;
+    Ok(())
+}
+
+fn format_highlight(highlight: &Highlight) -> String {
+    let mut description = format!("`{}`", highlight.trigger);
+    if highlight.is_regex {
+        description.push_str(" (regex)");
+    }
+    if !highlight.include_channels.is_empty() {
+        let channels = highlight.include_channels.iter().map(|x| x.mention()).join(", ");
+        description.push_str(&format!(", only in {}", channels));
+    }
+    if !highlight.exclude_channels.is_empty() {
+        let channels = highlight.exclude_channels.iter().map(|x| x.mention()).join(", ");
+        description.push_str(&format!(", not in {}", channels));
+    }
+    description
+}
+
 /// When in an `ApplicationContext`, send the reply as a ephemeral message.
 /// When in a `PrefixContext`, attempt to send the message in DMs,
 /// and give a generic error message when the user doesn't allow for that.
@@ -0,0 +1,194 @@
+use poise::serenity_prelude::GuildId;
+
+use super::*;
+
+/// Control the link filter, which deletes links to blocked domains and invites to other servers.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands(
+        "linkfilter_block",
+        "linkfilter_allow",
+        "linkfilter_remove",
+        "linkfilter_allow_invite",
+        "linkfilter_remove_invite",
+        "linkfilter_list",
+    )
+)]
+pub async fn linkfilter(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Block links to a domain and all of its subdomains
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "block"
+)]
+pub async fn linkfilter_block(
+    ctx: Ctx<'_>,
+    #[description = "Domain to block, i.e.: discord-nitro.gift"] domain: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let domain = parse_domain(&domain)?;
+    db.set_link_filter_domain(&domain, false, ctx.author().id).await?;
+    ctx.say_success(format!("Links to `{}` are now blocked", domain)).await?;
+    Ok(())
+}
+
+/// Allow links to a domain, even if a parent domain is blocked
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "allow"
+)]
+pub async fn linkfilter_allow(
+    ctx: Ctx<'_>,
+    #[description = "Domain to allow"] domain: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let domain = parse_domain(&domain)?;
+    db.set_link_filter_domain(&domain, true, ctx.author().id).await?;
+    ctx.say_success(format!("Links to `{}` are now allowed", domain)).await?;
+    Ok(())
+}
+
+/// Remove a domain from the link filter
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove"
+)]
+pub async fn linkfilter_remove(
+    ctx: Ctx<'_>,
+    #[autocomplete = "autocomplete_linkfilter_domain"]
+    #[description = "Domain to remove"]
+    domain: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    if !db.remove_link_filter_domain(&domain).await? {
+        abort_with!("That domain is not in the link filter");
+    }
+    ctx.say_success(format!("Removed `{}` from the link filter", domain)).await?;
+    Ok(())
+}
+
+/// Allow invites to another server
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "allow_invite"
+)]
+pub async fn linkfilter_allow_invite(
+    ctx: Ctx<'_>,
+    #[description = "An invite to the server, or its id"] invite: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let guild_id = resolve_invite_guild(ctx, &invite).await?;
+    db.add_allowed_invite_guild(guild_id, ctx.author().id).await?;
+    ctx.say_success(format!("Invites to the server `{}` are now allowed", guild_id)).await?;
+    Ok(())
+}
+
+/// Stop allowing invites to another server
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove_invite"
+)]
+pub async fn linkfilter_remove_invite(
+    ctx: Ctx<'_>,
+    #[description = "An invite to the server, or its id"] invite: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let guild_id = resolve_invite_guild(ctx, &invite).await?;
+    if !db.remove_allowed_invite_guild(guild_id).await? {
+        abort_with!("Invites to that server were not allowed");
+    }
+    ctx.say_success(format!("Invites to the server `{}` are no longer allowed", guild_id)).await?;
+    Ok(())
+}
+
+/// Show the link filter entries
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "list"
+)]
+pub async fn linkfilter_list(ctx: Ctx<'_>) -> Res<()> {
+    let config = ctx.get_config();
+    let db = ctx.get_db();
+    let filter = db.get_link_filter().await?;
+
+    let is_in_mod_bot_stuff = ctx.channel_id() == config.channel_mod_bot_stuff;
+    let format_list = |entries: Vec<String>| {
+        if entries.is_empty() {
+            "None".to_string()
+        } else {
+            util::ellipsis_text(&entries.join("\n"), 1024)
+        }
+    };
+
+    ctx.send_embed_full(!is_in_mod_bot_stuff, |e| {
+        e.title("Link filter");
+        e.field(
+            "Blocked domains",
+            format_list(filter.blocked_domains().sorted().map(|x| format!("`{}`", x)).collect()),
+            false,
+        );
+        e.field(
+            "Allowed domains",
+            format_list(filter.allowed_domains().sorted().map(|x| format!("`{}`", x)).collect()),
+            false,
+        );
+        e.field(
+            "Allowed invite servers",
+            format_list(
+                filter.allowed_invite_guilds.iter().sorted().map(|x| format!("`{}`", x)).collect(),
+            ),
+            false,
+        );
+    })
+    .await?;
+    Ok(())
+}
+
+async fn autocomplete_linkfilter_domain(ctx: Ctx<'_>, partial: &str) -> Vec<String> {
+    let db = ctx.get_db();
+    if let Ok(filter) = db.get_link_filter().await {
+        filter.domains.keys().filter(|x| x.contains(&partial)).cloned().sorted().collect_vec()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Accept either a plain domain or a full URL, and return the lowercase domain.
+fn parse_domain(value: &str) -> Res<String> {
+    let value = value.trim();
+    let domain =
+        util::url_domain(value).or_else(|| util::url_domain(&format!("https://{}", value)));
+    match domain {
+        Some(domain) if domain.contains('.') => Ok(domain.to_lowercase()),
+        _ => abort_with!("That's not a valid domain"),
+    }
+}
+
+async fn resolve_invite_guild(ctx: Ctx<'_>, invite: &str) -> Res<GuildId> {
+    if let Ok(id) = invite.trim().parse::<u64>() {
+        return Ok(GuildId(id));
+    }
+    let invite = ctx
+        .discord()
+        .http
+        .get_invite(invite.trim(), false, false, None)
+        .await
+        .user_error("Couldn't resolve that invite")?;
+    Ok(invite.guild.user_error("That invite doesn't lead to a server")?.id)
+}
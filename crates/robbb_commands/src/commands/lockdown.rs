@@ -0,0 +1,423 @@
+use anyhow::Context;
+use chrono::Utc;
+use poise::serenity_prelude::{
+    Channel, ChannelId, ChannelType, GuildChannel, PermissionOverwrite, PermissionOverwriteType,
+    RoleId,
+};
+use robbb_db::lockdown::{Lockdown, LockdownOverwrite};
+use robbb_util::log_error;
+use serenity::client;
+
+use crate::modlog;
+
+use super::*;
+
+/// Lock channels, so that only staff can send messages in them.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("lockdown_channel", "lockdown_category", "lockdown_preset")
+)]
+pub async fn lockdown(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Lock a single channel.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "channel"
+)]
+pub async fn lockdown_channel(
+    ctx: Ctx<'_>,
+    #[description = "Channel to lock (defaults to the current channel)"] channel: Option<Channel>,
+    #[description = "Automatically unlock after this time"] duration: Option<humantime::Duration>,
+    #[description = "Reason"] reason: Option<String>,
+) -> Res<()> {
+    let channel = channel.map(|x| x.id()).unwrap_or_else(|| ctx.channel_id());
+    do_lockdown(ctx, vec![channel], duration, reason).await
+}
+
+/// Lock all text channels in a category.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "category"
+)]
+pub async fn lockdown_category(
+    ctx: Ctx<'_>,
+    #[description = "Category to lock"] category: Channel,
+    #[description = "Automatically unlock after this time"] duration: Option<humantime::Duration>,
+    #[description = "Reason"] reason: Option<String>,
+) -> Res<()> {
+    let channels = channels_in_category(ctx, category).await?;
+    do_lockdown(ctx, channels, duration, reason).await
+}
+
+/// Lock a preconfigured group of channels.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "preset"
+)]
+pub async fn lockdown_preset(
+    ctx: Ctx<'_>,
+    #[description = "Name of the preset"]
+    #[autocomplete = "autocomplete_lockdown_preset"]
+    preset: String,
+    #[description = "Automatically unlock after this time"] duration: Option<humantime::Duration>,
+    #[description = "Reason"] reason: Option<String>,
+) -> Res<()> {
+    let channels = preset_channels(ctx, &preset)?;
+    do_lockdown(ctx, channels, duration, reason).await
+}
+
+/// Unlock channels that were previously locked.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("unlock_channel", "unlock_category", "unlock_preset", "unlock_all")
+)]
+pub async fn unlock(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Unlock a single channel.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "channel"
+)]
+pub async fn unlock_channel(
+    ctx: Ctx<'_>,
+    #[description = "Channel to unlock (defaults to the current channel)"] channel: Option<Channel>,
+) -> Res<()> {
+    let channel = channel.map(|x| x.id()).unwrap_or_else(|| ctx.channel_id());
+    do_unlock(ctx, vec![channel]).await
+}
+
+/// Unlock all text channels in a category.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "category"
+)]
+pub async fn unlock_category(
+    ctx: Ctx<'_>,
+    #[description = "Category to unlock"] category: Channel,
+) -> Res<()> {
+    let channels = channels_in_category(ctx, category).await?;
+    do_unlock(ctx, channels).await
+}
+
+/// Unlock a preconfigured group of channels.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "preset"
+)]
+pub async fn unlock_preset(
+    ctx: Ctx<'_>,
+    #[description = "Name of the preset"]
+    #[autocomplete = "autocomplete_lockdown_preset"]
+    preset: String,
+) -> Res<()> {
+    let channels = preset_channels(ctx, &preset)?;
+    do_unlock(ctx, channels).await
+}
+
+/// Unlock every currently locked channel.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "all"
+)]
+pub async fn unlock_all(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let channels = db.get_active_lockdowns().await?.into_iter().map(|x| x.channel_id).collect();
+    do_unlock(ctx, channels).await
+}
+
+async fn autocomplete_lockdown_preset(ctx: Ctx<'_>, partial: &str) -> Vec<String> {
+    let config = ctx.get_config();
+    config.lockdown_presets.keys().filter(|x| x.contains(partial)).cloned().sorted().collect_vec()
+}
+
+fn preset_channels(ctx: Ctx<'_>, preset: &str) -> Res<Vec<ChannelId>> {
+    let config = ctx.get_config();
+    Ok(config.lockdown_presets.get(preset).user_error("No such lockdown preset")?.clone())
+}
+
+async fn channels_in_category(ctx: Ctx<'_>, category: Channel) -> Res<Vec<ChannelId>> {
+    let category = match category {
+        Channel::Category(category) => category,
+        _ => abort_with!("That's not a category"),
+    };
+    let guild = ctx.guild().context("Failed to load guild")?;
+    let channels = guild
+        .channels
+        .values()
+        .filter_map(|x| x.clone().guild())
+        .filter(|x| x.parent_id == Some(category.id) && is_lockable(x))
+        .sorted_by_key(|x| x.position)
+        .map(|x| x.id)
+        .collect_vec();
+    if channels.is_empty() {
+        abort_with!("There are no text channels in that category");
+    }
+    Ok(channels)
+}
+
+fn is_lockable(channel: &GuildChannel) -> bool {
+    matches!(channel.kind, ChannelType::Text | ChannelType::News | ChannelType::Forum)
+}
+
+async fn do_lockdown(
+    ctx: Ctx<'_>,
+    channels: Vec<ChannelId>,
+    duration: Option<humantime::Duration>,
+    reason: Option<String>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let end_time =
+        duration.map(|x| chrono::Duration::from_std(*x)).transpose()?.map(|x| Utc::now() + x);
+
+    ctx.defer().await?;
+    let (mut locked, mut failed) = (Vec::new(), Vec::new());
+    for channel_id in channels {
+        if db.get_active_lockdown(channel_id).await?.is_some() {
+            continue;
+        }
+        let result =
+            lock_channel(ctx.discord(), ctx.author().id, channel_id, reason.clone(), end_time)
+                .await;
+        match result {
+            Ok(_) => locked.push(channel_id),
+            Err(err) => {
+                tracing::error!(error.message = %err, "Failed to lock {}: {:?}", channel_id, err);
+                failed.push(channel_id);
+            }
+        }
+    }
+    if locked.is_empty() && failed.is_empty() {
+        abort_with!("All of these channels are already locked");
+    }
+    if locked.is_empty() {
+        abort_with!(UserErr::Other(format!(
+            "Failed to lock {}",
+            failed.iter().map(|x| x.mention()).join(", ")
+        )));
+    }
+
+    modlog::log_lockdown(ctx, &locked, &failed, end_time, reason.as_deref()).await;
+    ctx.say_success_mod_action(format!(
+        "Locked {}{}{}",
+        locked.iter().map(|x| x.mention()).join(", "),
+        end_time.map(|x| format!(" until {}", util::format_date_detailed(x))).unwrap_or_default(),
+        failed_channels_note("lock", &failed),
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn do_unlock(ctx: Ctx<'_>, channels: Vec<ChannelId>) -> Res<()> {
+    let db = ctx.get_db();
+
+    ctx.defer().await?;
+    let (mut unlocked, mut failed) = (Vec::new(), Vec::new());
+    for channel_id in channels {
+        if let Some(lockdown) = db.get_active_lockdown(channel_id).await? {
+            match unlock_channel_lockdown(ctx.discord(), &lockdown).await {
+                Ok(()) => unlocked.push(channel_id),
+                Err(err) => {
+                    tracing::error!(error.message = %err, "Failed to unlock {}: {:?}", channel_id, err);
+                    failed.push(channel_id);
+                }
+            }
+        }
+    }
+    if unlocked.is_empty() && failed.is_empty() {
+        abort_with!("None of these channels are locked");
+    }
+    if unlocked.is_empty() {
+        abort_with!(UserErr::Other(format!(
+            "Failed to unlock {}",
+            failed.iter().map(|x| x.mention()).join(", ")
+        )));
+    }
+
+    modlog::log_unlock(ctx, &unlocked).await;
+    ctx.say_success_mod_action(format!(
+        "Unlocked {}{}",
+        unlocked.iter().map(|x| x.mention()).join(", "),
+        failed_channels_note("unlock", &failed),
+    ))
+    .await?;
+    Ok(())
+}
+
+fn failed_channels_note(action: &str, failed: &[ChannelId]) -> String {
+    if failed.is_empty() {
+        String::new()
+    } else {
+        format!("\nFailed to {} {}", action, failed.iter().map(|x| x.mention()).join(", "))
+    }
+}
+
+/// Permissions that @everyone loses while a channel is locked, and that staff explicitly keeps.
+fn locked_permissions() -> Permissions {
+    Permissions::SEND_MESSAGES
+        | Permissions::SEND_MESSAGES_IN_THREADS
+        | Permissions::CREATE_PUBLIC_THREADS
+        | Permissions::CREATE_PRIVATE_THREADS
+        | Permissions::ADD_REACTIONS
+}
+
+/// Store the current role overwrites of the channel in the database,
+/// then deny sending messages for @everyone while explicitly allowing it for staff.
+pub async fn lock_channel(
+    ctx: &client::Context,
+    moderator: UserId,
+    channel_id: ChannelId,
+    reason: Option<String>,
+    end_time: Option<chrono::DateTime<Utc>>,
+) -> anyhow::Result<Lockdown> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let channel = channel_id.to_channel(&ctx).await?.guild().context("Not a guild channel")?;
+
+    let everyone = RoleId(config.guild.0);
+    let original_overwrites = [everyone, config.role_mod, config.role_helper]
+        .into_iter()
+        .map(|role_id| LockdownOverwrite {
+            role_id,
+            permissions: channel
+                .permission_overwrites
+                .iter()
+                .find(|x| x.kind == PermissionOverwriteType::Role(role_id))
+                .map(|x| (x.allow, x.deny)),
+        })
+        .collect_vec();
+
+    let lockdown = db
+        .add_lockdown(
+            channel_id,
+            moderator,
+            reason.clone(),
+            Utc::now(),
+            end_time,
+            original_overwrites.clone(),
+        )
+        .await?;
+
+    if let Err(err) = apply_locked_overwrites(ctx, channel_id, everyone, &original_overwrites).await
+    {
+        // don't leave a half-locked channel behind. If restoring fails as well,
+        // the lockdown stays active so that /unlock can try again.
+        match restore_overwrites(ctx, &lockdown).await {
+            Ok(()) => db.set_lockdown_inactive(lockdown.id).await?,
+            Err(restore_err) => {
+                tracing::error!(error.message = %restore_err, "Failed to restore overwrites of {} after failed lockdown: {:?}", channel_id, restore_err);
+            }
+        }
+        return Err(err);
+    }
+
+    let result = channel_id
+        .send_embed(ctx, |e| {
+            e.title("🔒 This channel has been locked");
+            e.description(
+                "The moderators have temporarily locked this channel. Please be patient.",
+            );
+            if let Some(reason) = &reason {
+                e.field("Reason", reason, false);
+            }
+            if let Some(end_time) = end_time {
+                e.field("Unlocks", util::format_date_detailed(end_time), false);
+            }
+        })
+        .await;
+    log_error!(result);
+    Ok(lockdown)
+}
+
+async fn apply_locked_overwrites(
+    ctx: &client::Context,
+    channel_id: ChannelId,
+    everyone: RoleId,
+    original_overwrites: &[LockdownOverwrite],
+) -> anyhow::Result<()> {
+    for overwrite in original_overwrites {
+        let (allow, deny) =
+            overwrite.permissions.unwrap_or((Permissions::empty(), Permissions::empty()));
+        let (allow, deny) = if overwrite.role_id == everyone {
+            (allow - locked_permissions(), deny | locked_permissions())
+        } else {
+            (allow | locked_permissions(), deny - locked_permissions())
+        };
+        channel_id
+            .create_permission(
+                &ctx,
+                &PermissionOverwrite {
+                    allow,
+                    deny,
+                    kind: PermissionOverwriteType::Role(overwrite.role_id),
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Set the permission overwrites of a locked channel back to what they were before the lockdown.
+async fn restore_overwrites(ctx: &client::Context, lockdown: &Lockdown) -> anyhow::Result<()> {
+    for overwrite in lockdown.original_overwrites.iter() {
+        let kind = PermissionOverwriteType::Role(overwrite.role_id);
+        match overwrite.permissions {
+            Some((allow, deny)) => {
+                lockdown
+                    .channel_id
+                    .create_permission(&ctx, &PermissionOverwrite { allow, deny, kind })
+                    .await?
+            }
+            None => lockdown.channel_id.delete_permission(&ctx, kind).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Restore the permission overwrites of a locked channel exactly as they were before the lockdown.
+/// If the channel got deleted in the meantime, the lockdown is just marked as ended.
+pub async fn unlock_channel_lockdown(
+    ctx: &client::Context,
+    lockdown: &Lockdown,
+) -> anyhow::Result<()> {
+    let db = ctx.get_db().await;
+    if let Err(err) = restore_overwrites(ctx, lockdown).await {
+        match lockdown.channel_id.to_channel(&ctx).await {
+            Err(channel_err) if util::is_not_found(&channel_err) => {
+                db.set_lockdown_inactive(lockdown.id).await?;
+                return Ok(());
+            }
+            _ => return Err(err),
+        }
+    }
+    db.set_lockdown_inactive(lockdown.id).await?;
+
+    let result = lockdown
+        .channel_id
+        .send_embed(ctx, |e| {
+            e.title("🔓 This channel has been unlocked");
+        })
+        .await;
+    log_error!(result);
+    Ok(())
+}
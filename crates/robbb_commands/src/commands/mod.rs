@@ -1,8 +1,14 @@
 use itertools::Itertools;
-use poise::serenity_prelude::{Guild, Mentionable, UserId};
+use poise::serenity_prelude::{
+    application::component::ButtonStyle,
+    interaction::{message_component::MessageComponentInteraction, InteractionResponseType},
+    AttachmentType, ChannelId, CreateEmbed, Guild, Mentionable, UserId,
+};
 use poise::serenity_prelude::{Member, Permissions};
 use poise::Command;
 use robbb_util::abort_with;
+use robbb_util::charts::{self, ChartKind};
+use robbb_util::collect_interaction;
 use robbb_util::extensions::*;
 use robbb_util::prelude::*;
 use robbb_util::util;
@@ -13,14 +19,19 @@ pub use errors::*;
 use crate::checks::PermissionLevel;
 
 pub mod ask;
+pub mod autorespond;
+pub mod autoslowmode;
 pub mod ban;
 pub mod blocklist;
 pub mod emojistats;
 pub mod fetch;
 pub mod help;
+pub mod highlightlimit;
 pub mod highlights;
 pub mod info;
 pub mod kick;
+pub mod linkfilter;
+pub mod lockdown;
 pub mod modping;
 pub mod move_users;
 pub mod mute;
@@ -29,11 +40,14 @@ pub mod pfp;
 pub mod poise_commands;
 pub mod poll;
 pub mod purge;
+pub mod raidmode;
 pub mod role;
 pub mod small;
 pub mod tag;
 pub mod top;
+pub mod topalias;
 pub mod unban;
+pub mod verification;
 pub mod version;
 pub mod warn;
 
@@ -48,6 +62,8 @@ pub fn all_commands() -> Vec<poise::Command<UserData, Error>> {
         poll::poll(),
         tag::tag(),
         tag::taglist(),
+        tag::tag_search(),
+        tag::proposetag(),
         ask::ask(),
         modping::modping(),
         highlights::highlights(),
@@ -59,8 +75,10 @@ pub fn all_commands() -> Vec<poise::Command<UserData, Error>> {
         small::git(),
         small::dotfiles(),
         fetch::fetch(),
+        fetch::fetch_history(),
         fetch::set_fetch(),
         top::top(),
+        top::top_trend(),
         move_users::move_users(),
         // Mod-only
         info::modinfo(),
@@ -76,6 +94,15 @@ pub fn all_commands() -> Vec<poise::Command<UserData, Error>> {
         note::note(),
         mute::mute(),
         purge::purge(),
+        raidmode::raidmode(),
+        lockdown::lockdown(),
+        lockdown::unlock(),
+        autoslowmode::autoslowmode(),
+        linkfilter::linkfilter(),
+        autorespond::autorespond(),
+        topalias::topalias(),
+        highlightlimit::highlightlimit(),
+        verification::verification(),
         poise_commands::register(),
         poise_commands::delete(),
         // context menu
@@ -146,6 +173,143 @@ pub async fn member_or_self(ctx: Ctx<'_>, member: Option<Member>) -> Res<Member>
     }
 }
 
+const CONFIRM_BUTTON: &str = "confirm";
+const CANCEL_BUTTON: &str = "cancel";
+
+/// Show a preview of an action and wait for the author of the command to confirm it.
+/// Returns false if they cancel or don't react in time.
+pub async fn prompt_confirmation(ctx: Ctx<'_>, preview: CreateEmbed) -> Res<bool> {
+    let handle = ctx
+        .send(|m| {
+            m.embed(|e| {
+                *e = preview;
+                e
+            });
+            m.components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.label("Confirm").style(ButtonStyle::Success).custom_id(CONFIRM_BUTTON)
+                    });
+                    r.create_button(|b| {
+                        b.label("Cancel").style(ButtonStyle::Secondary).custom_id(CANCEL_BUTTON)
+                    })
+                })
+            })
+        })
+        .await?;
+    let mut message = handle.message().await?;
+
+    let mut interactions = collect_interaction::await_component_interactions_by(
+        ctx.discord(),
+        &message,
+        ctx.author().id,
+        1,
+        std::time::Duration::from_secs(120),
+    );
+    match interactions.next(ctx.discord()).await {
+        Some(interaction) => {
+            interaction
+                .create_interaction_response(&ctx.discord(), |ir| {
+                    ir.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| d.components(|c| c))
+                })
+                .await?;
+            Ok(interaction.data.custom_id == CONFIRM_BUTTON)
+        }
+        None => {
+            message.to_mut().edit(&ctx.discord(), |e| e.components(|c| c)).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// Respond to a button press with a message only the user that pressed it can see.
+pub async fn respond_ephemeral(
+    ctx: &serenity::client::Context,
+    interaction: &MessageComponentInteraction,
+    content: &str,
+) -> anyhow::Result<()> {
+    interaction
+        .create_interaction_response(&ctx, |ir| {
+            ir.kind(InteractionResponseType::ChannelMessageWithSource);
+            ir.interaction_response_data(|d| d.content(content).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Parse a list of channel mentions or ids, separated by spaces or commas.
+pub fn parse_channel_list(value: Option<&str>) -> Res<Vec<ChannelId>> {
+    let channels = value
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            serenity::utils::parse_channel(x)
+                .or_else(|| x.parse().ok())
+                .map(ChannelId)
+                .user_error("Channels must be given as channel mentions or ids")
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(channels)
+}
+
+/// Reply with an embed showing some statistics.
+/// If a kind of chart is given, the data is rendered as a chart that's shown in the embed,
+/// either in addition to or instead of the text.
+/// The `unit` is shown next to the values in the chart, i.e. `"%"` for percentages.
+pub async fn send_stats_embed(
+    ctx: Ctx<'_>,
+    title: String,
+    text: String,
+    chart: Option<ChartKind>,
+    chart_only: bool,
+    unit: &'static str,
+    data: Vec<(String, f64)>,
+) -> Res<()> {
+    let chart_png = match chart {
+        Some(_) if data.iter().all(|(_, value)| *value <= 0f64) => {
+            abort_with!("There is no data to show in a chart")
+        }
+        Some(kind) => {
+            let chart_title = title.clone();
+            let render = move || charts::render_chart(kind, &chart_title, unit, &data);
+            Some(tokio::task::spawn_blocking(render).await??)
+        }
+        None => None,
+    };
+
+    let embed = robbb_util::embeds::make_create_embed(ctx.discord(), |e| {
+        e.title(&title);
+        if chart_png.is_none() || !chart_only {
+            e.description(text);
+        }
+        if chart_png.is_some() {
+            e.image("attachment://chart.png");
+        }
+        e
+    })
+    .await;
+    ctx.send(|m| {
+        if let poise::Context::Prefix(prefix) = ctx {
+            m.reference_message(prefix.msg);
+        }
+        m.embed(|e| {
+            *e = embed;
+            e
+        });
+        if let Some(png) = chart_png {
+            m.attachment(AttachmentType::Bytes {
+                data: std::borrow::Cow::from(png),
+                filename: "chart.png".to_string(),
+            });
+        }
+        m
+    })
+    .await?;
+    Ok(())
+}
+
 pub struct CmdMeta {
     perms: PermissionLevel,
 }
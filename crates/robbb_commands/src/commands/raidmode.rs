@@ -0,0 +1,208 @@
+use chrono::Utc;
+use poise::serenity_prelude::VerificationLevel;
+use robbb_db::raid_mode::{RaidMode, RaidModeAction};
+use robbb_util::config::Config;
+use robbb_util::log_error;
+use serenity::client;
+
+use crate::modlog;
+
+use super::*;
+
+/// Control raid mode, which locks down the server for new members.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("raidmode_on", "raidmode_off", "raidmode_status")
+)]
+pub async fn raidmode(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Enable raid mode
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "on"
+)]
+pub async fn raidmode_on(
+    ctx: Ctx<'_>,
+    #[description = "How long raid mode should stay active"] duration: Option<humantime::Duration>,
+    #[description = "What should happen to new members"] action: Option<RaidModeAction>,
+) -> Res<()> {
+    let (config, db) = (ctx.get_config(), ctx.get_db());
+    if db.get_active_raid_mode().await?.is_some() {
+        abort_with!("Raid mode is already active");
+    }
+
+    let duration = duration.map(|x| *x).unwrap_or(config.raid_mode_duration);
+    let action = action.unwrap_or_else(|| default_raid_mode_action(&config));
+    if action == RaidModeAction::Quarantine && config.role_quarantine.is_none() {
+        abort_with!("There is no quarantine role configured, so new members can only be kicked");
+    }
+
+    ctx.defer().await?;
+    let raid_mode = enter_raid_mode(ctx.discord(), ctx.author().id, duration, action).await?;
+    ctx.say_success_mod_action(format!(
+        "Raid mode is now active until {}",
+        util::format_date_detailed(raid_mode.end_time)
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Disable raid mode
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "off"
+)]
+pub async fn raidmode_off(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let raid_mode = db.get_active_raid_mode().await?.user_error("Raid mode is not active")?;
+
+    ctx.defer().await?;
+    end_raid_mode(ctx.discord(), &raid_mode).await?;
+    ctx.say_success_mod_action("Raid mode has been disabled").await?;
+    Ok(())
+}
+
+/// Check whether raid mode is active
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "status"
+)]
+pub async fn raidmode_status(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    match db.get_active_raid_mode().await? {
+        Some(raid_mode) => {
+            ctx.send_embed_full(true, |e| {
+                e.title("Raid mode is active");
+                e.field("Enabled by", raid_mode.moderator.mention(), true);
+                e.field("New members", raid_mode.action, true);
+                e.field("Since", util::format_date_detailed(raid_mode.start_time), false);
+                e.field("Until", util::format_date_detailed(raid_mode.end_time), false);
+            })
+            .await?;
+        }
+        None => {
+            ctx.say_success("Raid mode is not active").await?;
+        }
+    }
+    Ok(())
+}
+
+/// The action raid mode uses when none is specified explicitly.
+/// Quarantining is preferred, but requires a quarantine role to be configured.
+pub fn default_raid_mode_action(config: &Config) -> RaidModeAction {
+    if config.role_quarantine.is_some() {
+        RaidModeAction::Quarantine
+    } else {
+        RaidModeAction::Kick
+    }
+}
+
+/// Raise the guilds verification level, store the raid mode in the database and log it to #bot-auto-mod.
+pub async fn enter_raid_mode(
+    ctx: &client::Context,
+    moderator: UserId,
+    duration: std::time::Duration,
+    action: RaidModeAction,
+) -> anyhow::Result<RaidMode> {
+    let (config, db) = ctx.get_config_and_db().await;
+
+    let previous_verification_level = config.guild.to_partial_guild(&ctx).await?.verification_level;
+    let start_time = Utc::now();
+    let end_time = start_time + chrono::Duration::from_std(duration)?;
+
+    // only store the raid mode once the guild actually got locked down,
+    // so a failed edit doesn't leave an active raid mode behind.
+    let mut guild_id = config.guild;
+    guild_id.edit(&ctx, |g| g.verification_level(VerificationLevel::Higher)).await?;
+
+    let raid_mode = db
+        .start_raid_mode(
+            moderator,
+            action,
+            previous_verification_level.num() as i64,
+            start_time,
+            end_time,
+        )
+        .await;
+    let raid_mode = match raid_mode {
+        Ok(raid_mode) => raid_mode,
+        Err(err) => {
+            let result =
+                guild_id.edit(&ctx, |g| g.verification_level(previous_verification_level)).await;
+            log_error!("Failed to restore the verification level", result);
+            return Err(err);
+        }
+    };
+
+    modlog::log_raid_mode_started(ctx, &raid_mode).await;
+    Ok(raid_mode)
+}
+
+/// Restore the guilds previous verification level, mark the raid mode as inactive
+/// and release the members that got quarantined by it.
+pub async fn end_raid_mode(ctx: &client::Context, raid_mode: &RaidMode) -> anyhow::Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    db.set_raid_mode_inactive(raid_mode.id).await?;
+
+    if let Some(level) = verification_level_from_num(raid_mode.previous_verification_level) {
+        let mut guild_id = config.guild;
+        guild_id.edit(&ctx, |g| g.verification_level(level)).await?;
+    }
+
+    let released = release_quarantined_members(ctx, raid_mode).await?;
+    modlog::log_raid_mode_ended(ctx, raid_mode, &released).await;
+    Ok(())
+}
+
+/// Remove the quarantine role from the members that got quarantined by the raid mode.
+/// Members that still have to pass verification stay quarantined,
+/// and members that left are skipped.
+/// Returns the members that were released.
+async fn release_quarantined_members(
+    ctx: &client::Context,
+    raid_mode: &RaidMode,
+) -> anyhow::Result<Vec<UserId>> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let role_quarantine = match config.role_quarantine {
+        Some(role) => role,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut released = Vec::new();
+    for user_id in db.get_raid_mode_quarantined(raid_mode.id).await? {
+        if db.get_pending_verification(user_id).await?.is_some() {
+            continue;
+        }
+        let result = match config.guild.member(&ctx, user_id).await {
+            Ok(mut member) => member.remove_role(&ctx, role_quarantine).await,
+            Err(err) if util::is_not_found(&err) => continue,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => released.push(user_id),
+            err => log_error!("Failed to release a quarantined member", err),
+        }
+    }
+    Ok(released)
+}
+
+fn verification_level_from_num(n: i64) -> Option<VerificationLevel> {
+    match n {
+        0 => Some(VerificationLevel::None),
+        1 => Some(VerificationLevel::Low),
+        2 => Some(VerificationLevel::Medium),
+        3 => Some(VerificationLevel::High),
+        4 => Some(VerificationLevel::Higher),
+        _ => None,
+    }
+}
@@ -1,8 +1,59 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use anyhow::Context;
 use chrono::Utc;
-use poise::Modal;
+use poise::{
+    serenity_prelude::{
+        application::component::{ActionRowComponent, ButtonStyle, InputTextStyle},
+        interaction::{Interaction, InteractionResponseType},
+        Attachment, AttachmentType, CollectModalInteraction, CreateComponents, CreateEmbed, User,
+    },
+    Modal,
+};
+use robbb_db::{
+    tag::{RichTagContent, RichTagField, Tag, TagLinkButton, TagNames},
+    tag_proposal::TagProposal,
+    tag_usage::TagUsageStats,
+};
+use robbb_util::{
+    embeds, log_error,
+    tag_archive::{self, TagFile},
+    tag_template::{self, TemplateContext},
+};
+use serenity::client;
+use similar::{ChangeTag, TextDiff};
 use tracing_futures::Instrument;
 
 use super::*;
+use crate::checks::{self, PermissionLevel};
+
+/// Maximum length of the list of tags shown in a single field of `/taglist`.
+const MAX_TAGLIST_FIELD_LENGTH: usize = 450;
+
+/// Maximum length of a single diff shown in `/settag history`.
+const MAX_DIFF_LENGTH: usize = 400;
+
+/// Maximum size of an archive uploaded to `/settag import`.
+const MAX_TAG_ARCHIVE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Maximum amount of tags listed in `/tagsearch`.
+const TAG_SEARCH_RESULT_COUNT: i64 = 10;
+
+/// Amount of tags listed as most and least used in `/settag stats`.
+const TAG_STATS_LIST_LENGTH: usize = 10;
+
+/// Maximum amount of link buttons on a tag, as they have to fit into a single row.
+const MAX_TAG_BUTTONS: usize = 5;
+const MAX_TAG_FIELDS: usize = 25;
+
+/// Discords limit for the combined length of all text in an embed.
+const MAX_EMBED_LENGTH: usize = 6000;
+/// Length reserved for the footer of tag embeds, which names the author of the tag.
+const TAG_FOOTER_RESERVED_LENGTH: usize = 100;
 
 /// Get the text stored in a tag
 #[poise::command(
@@ -17,6 +68,9 @@ pub async fn tag(
     #[autocomplete = "tag_autocomplete_existing"]
     #[rename = "tag"]
     tag_name: String,
+    #[description = "Arguments for the tag, such as a user to mention"]
+    #[rest]
+    args: Option<String>,
 ) -> Res<()> {
     let db = ctx.get_db();
 
@@ -24,16 +78,32 @@ pub async fn tag(
 
     let moderator = tag.moderator.to_user(&ctx.discord()).await?;
 
-    if util::validate_url(&tag.content) {
-        ctx.say(&tag.content).await?;
+    log_error!(db.record_tag_usage(&tag.name, ctx.channel_id()).await);
+
+    let template_ctx = TemplateContext::new(ctx.author().id, ctx.channel_id(), args.as_deref());
+    let rendered = tag_template::render_tag_content(&tag.content, tag.templated, &template_ctx);
+
+    // plain links are sent as-is, so discord shows a preview of them
+    if tag.rich_content.is_none() && util::validate_url(&rendered.text) {
+        ctx.say(&rendered.text).await?;
     } else {
-        ctx.send_embed(|e| {
-            e.title(&tag.name);
-            e.description(&tag.content);
-            e.footer(|f| f.text(format!("Written by {}", moderator.tag())));
-            if let Some(date) = tag.create_date {
-                e.timestamp(date);
+        let embed = make_tag_embed(ctx.discord(), &tag, &moderator, &rendered.text).await;
+        let buttons = tag.rich_content.as_ref().map_or(&[][..], |x| x.buttons.as_slice());
+        ctx.send(|m| {
+            if let poise::Context::Prefix(prefix) = ctx {
+                m.reference_message(prefix.msg);
+            }
+            // mentions inside of embeds don't ping, so the users mentioned in the arguments
+            // get mentioned in the message content as well
+            if !rendered.mentioned_users.is_empty() {
+                m.content(rendered.mentioned_users.iter().map(|x| x.mention()).join(" "));
+                m.allowed_mentions(|a| a.empty_parse().users(rendered.mentioned_users.clone()));
             }
+            if !buttons.is_empty() {
+                m.components = Some(make_link_buttons(buttons));
+            }
+            m.embeds.push(embed);
+            m
         })
         .await?;
     }
@@ -41,33 +111,181 @@ pub async fn tag(
     Ok(())
 }
 
-/// Get the names of all tags
+/// Build the embed showing a tag, given its already rendered text.
+pub async fn make_tag_embed(
+    ctx: &client::Context,
+    tag: &Tag,
+    author: &User,
+    text: &str,
+) -> CreateEmbed {
+    embeds::make_create_embed(ctx, |e| {
+        e.description(util::ellipsis_text(text, 4096));
+        if tag.official {
+            e.footer(|f| f.text(format!("Written by {}", author.tag())));
+        } else {
+            e.footer(|f| {
+                f.text(format!("Community tag by {}, not reviewed for accuracy", author.tag()))
+            });
+        }
+        if let Some(date) = tag.create_date {
+            e.timestamp(date);
+        }
+        match &tag.rich_content {
+            Some(rich) => {
+                e.title(rich.title.as_deref().unwrap_or(&tag.name));
+                for field in &rich.fields {
+                    e.field(&field.name, &field.value, field.inline);
+                }
+                if let Some(url) = &rich.image_url {
+                    e.image(url);
+                }
+                if let Some(url) = &rich.thumbnail_url {
+                    e.thumbnail(url);
+                }
+                if let Some(color) = rich.color {
+                    e.color(color);
+                }
+            }
+            None => {
+                e.title(&tag.name);
+            }
+        }
+        e
+    })
+    .await
+}
+
+pub fn make_link_buttons(buttons: &[TagLinkButton]) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    components.create_action_row(|r| {
+        for button in buttons {
+            r.create_button(|b| b.style(ButtonStyle::Link).label(&button.label).url(&button.url));
+        }
+        r
+    });
+    components
+}
+
+/// Search through the names and content of all tags
 #[poise::command(
     slash_command,
     guild_only,
     prefix_command,
     custom_data = "CmdMeta { perms: PermissionLevel::User }",
-    rename = "taglist"
+    rename = "tagsearch"
 )]
-pub async fn taglist(ctx: Ctx<'_>) -> Res<()> {
+pub async fn tag_search(
+    ctx: Ctx<'_>,
+    #[description = "What you're looking for"]
+    #[rest]
+    query: String,
+) -> Res<()> {
     let db = ctx.get_db();
-
-    let tags = db.list_tags().await?;
+    let results = db.search_tags(&query, TAG_SEARCH_RESULT_COUNT).await?;
+    if results.is_empty() {
+        abort_with!("No tags matched your search");
+    }
 
     ctx.send_embed(|e| {
-        e.title("Tags").description(&tags.join(", "));
+        e.title(format!("Tags matching \"{}\"", util::ellipsis_text(&query, 200)));
+        e.description("Use `/tag <name>` to show one of them");
+        for result in results {
+            e.field(result.name, util::ellipsis_text(&result.snippet, 1024), false);
+        }
     })
     .await?;
+    Ok(())
+}
+
+/// Get the names of all tags
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::User }",
+    rename = "taglist"
+)]
+pub async fn taglist(
+    ctx: Ctx<'_>,
+    #[description = "Only show official tags, hiding the ones proposed by members"]
+    official: Option<bool>,
+) -> Res<()> {
+    let db = ctx.get_db();
+
+    let tags = db.list_tags_with_category(official.unwrap_or(false)).await?;
+    let aliases_by_tag = db
+        .list_tag_names()
+        .await?
+        .aliases
+        .into_iter()
+        .map(|(alias, tag)| (tag.to_lowercase(), alias))
+        .into_group_map();
+
+    let by_category = tags.into_iter().into_group_map_by(|(_, category)| category.clone());
+    let fields = by_category
+        .into_iter()
+        .sorted_by_key(|(category, _)| (category.is_none(), category.clone()))
+        .flat_map(|(category, tags)| {
+            let entries =
+                tags.into_iter().map(|(name, _)| match aliases_by_tag.get(&name.to_lowercase()) {
+                    Some(aliases) => format!("{} ({})", name, aliases.iter().sorted().join(", ")),
+                    None => name,
+                });
+            let title = category.unwrap_or_else(|| "Uncategorized".to_string());
+            split_tag_list(entries).into_iter().enumerate().map(move |(idx, list)| {
+                if idx == 0 {
+                    (title.clone(), list)
+                } else {
+                    (format!("{} (continued)", title), list)
+                }
+            })
+        })
+        .collect_vec();
+
+    let base_embed = embeds::make_create_embed(ctx.discord(), |e| e).await;
+    embeds::PaginatedEmbed::create_from_fields("Tags".to_string(), fields, base_embed)
+        .await
+        .reply_to(ctx, false)
+        .await?;
 
     Ok(())
 }
 
+/// Join the entries of a tag list, splitting it up into chunks that fit into an embed field.
+fn split_tag_list(entries: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for entry in entries {
+        if !current.is_empty() && current.len() + entry.len() + 2 > MAX_TAGLIST_FIELD_LENGTH {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str(", ");
+        }
+        current.push_str(&entry);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
 /// Manage tags
 #[poise::command(
     slash_command,
     guild_only,
     custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
-    subcommands("tag_set", "tag_delete")
+    subcommands(
+        "tag_set",
+        "tag_delete",
+        "tag_category",
+        "tag_alias",
+        "tag_history",
+        "tag_rollback",
+        "tag_stats",
+        "tag_export",
+        "tag_import"
+    )
 )]
 pub async fn settag(_ctx: Ctx<'_>) -> Res<()> {
     Ok(())
@@ -89,11 +307,623 @@ pub async fn tag_delete(
     tag_name: String,
 ) -> Res<()> {
     let db = ctx.get_db();
-    db.delete_tag(tag_name).await?;
+    let tag = db.get_tag(&tag_name).await?.user_error("No tag with this name exists")?;
+    // get_tag resolves aliases, which shouldn't take the whole tag with them
+    if !tag.name.eq_ignore_ascii_case(&tag_name) {
+        abort_with!(UserErr::Other(format!(
+            "`{}` is an alias of the tag `{}`. Use `/settag alias remove` to remove the alias",
+            tag_name, tag.name
+        )));
+    }
+    db.delete_tag(tag.name).await?;
     ctx.say_success("Succesfully removed!").await?;
     Ok(())
 }
 
+/// Set or clear the category of a tag
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "category"
+)]
+pub async fn tag_category(
+    ctx: Ctx<'_>,
+    #[rename = "name"]
+    #[description = "Name of the tag"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag_name: String,
+    #[description = "The category, such as wayland or rules. Leave empty to remove the category"]
+    #[autocomplete = "tag_category_autocomplete"]
+    category: Option<String>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let tag = db.get_tag(&tag_name).await?.user_error("No tag with this name exists")?;
+    let category = category.map(|x| x.trim().to_lowercase()).filter(|x| !x.is_empty());
+    db.set_tag_category(&tag.name, category.clone()).await?;
+    match category {
+        Some(category) => {
+            ctx.say_success(format!("Moved {} into the category {}", tag.name, category)).await?
+        }
+        None => ctx.say_success(format!("Removed {} from its category", tag.name)).await?,
+    };
+    Ok(())
+}
+
+/// Show the changes made to a tag over time
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "history"
+)]
+pub async fn tag_history(
+    ctx: Ctx<'_>,
+    #[rename = "name"]
+    #[description = "Name of the tag"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag_name: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    // resolve aliases, but still allow looking at the history of deleted tags
+    let tag_name = match db.get_tag(&tag_name).await? {
+        Some(tag) => tag.name,
+        None => tag_name,
+    };
+    let revisions = db.get_tag_revisions(&tag_name).await?;
+    if revisions.is_empty() {
+        abort_with!("No tag with this name exists");
+    }
+
+    let fields = revisions
+        .iter()
+        .enumerate()
+        .map(|(idx, revision)| {
+            let previous = idx.checked_sub(1).map(|previous_idx| &revisions[previous_idx]);
+            let previous_content = previous.map_or("", |x| x.content.as_str());
+            let embed_changed = previous.map_or(revision.rich_content.is_some(), |x| {
+                x.rich_content != revision.rich_content
+            });
+            let embed_note =
+                if embed_changed { "\n*The embed content changed as well*" } else { "" };
+            (
+                format!("Revision {}", revision.revision),
+                format!(
+                    "{} by {}\n{}{}",
+                    util::format_date_ago(revision.create_date),
+                    revision.moderator.mention(),
+                    format_diff(previous_content, &revision.content),
+                    embed_note
+                ),
+            )
+        })
+        .rev()
+        .collect_vec();
+
+    let base_embed = embeds::make_create_embed(ctx.discord(), |e| {
+        e.description(format!(
+            "{} revisions of {}. Use `/settag rollback` to restore one of them.",
+            revisions.len(),
+            tag_name
+        ))
+    })
+    .await;
+    embeds::PaginatedEmbed::create_from_fields("Tag history".to_string(), fields, base_embed)
+        .await
+        .reply_to(ctx, false)
+        .await?;
+    Ok(())
+}
+
+/// Show the lines that changed between two versions of a tag.
+fn format_diff(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let changes = diff
+        .iter_all_changes()
+        .filter_map(|change| match change.tag() {
+            ChangeTag::Delete => Some(format!("- {}", change.value().trim_end())),
+            ChangeTag::Insert => Some(format!("+ {}", change.value().trim_end())),
+            ChangeTag::Equal => None,
+        })
+        .join("\n");
+    if changes.is_empty() {
+        "No changes".to_string()
+    } else {
+        // make sure the content can't close the code block
+        let changes = util::ellipsis_text(&changes, MAX_DIFF_LENGTH).replace("```", "`\u{200b}``");
+        format!("```diff\n{}\n```", changes)
+    }
+}
+
+/// Restore an earlier version of a tag
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "rollback"
+)]
+pub async fn tag_rollback(
+    ctx: Ctx<'_>,
+    #[rename = "name"]
+    #[description = "Name of the tag"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag_name: String,
+    #[description = "The revision to restore, as shown in /settag history"] revision: i64,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let existing_tag = db.get_tag(&tag_name).await?;
+    let tag_name = existing_tag.as_ref().map(|x| x.name.clone()).unwrap_or(tag_name);
+    let revision = db
+        .get_tag_revision(&tag_name, revision)
+        .await?
+        .user_error("This tag doesn't have a revision with that number")?;
+
+    // the rollback is stored as a new revision, so it can be undone as well
+    let official = existing_tag.map_or(true, |x| x.official);
+    db.set_tag(
+        ctx.author().id,
+        tag_name.clone(),
+        revision.content,
+        revision.rich_content,
+        official,
+        Some(Utc::now()),
+    )
+    .await?;
+    ctx.say_success(format!("Restored revision {} of {}", revision.revision, tag_name)).await?;
+    Ok(())
+}
+
+/// Show which tags are used, and which ones aren't
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "stats"
+)]
+pub async fn tag_stats(
+    ctx: Ctx<'_>,
+    #[description = "Only consider the last N days. Defaults to 30"] days: Option<u32>,
+    #[rename = "name"]
+    #[description = "Show in which channels this tag gets used"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag_name: Option<String>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let days = days.unwrap_or(30);
+    let since = (Utc::now() - chrono::Duration::days(days as i64)).naive_utc().date();
+
+    if let Some(tag_name) = tag_name {
+        let tag = db.get_tag(&tag_name).await?.user_error("No tag with this name exists")?;
+        let by_channel = db.get_tag_usage_by_channel(&tag.name, since).await?;
+        let total: i64 = by_channel.iter().map(|(_, uses)| uses).sum();
+        let channels = by_channel
+            .iter()
+            .take(20)
+            .map(|(channel_id, uses)| format!("{}: {}", channel_id.mention(), uses))
+            .join("\n");
+        ctx.send_embed(|e| {
+            e.title(format!("Usage of {} in the last {} days", tag.name, days));
+            e.description(format!("Used {} times", total));
+            if !channels.is_empty() {
+                e.field("Channels", channels, false);
+            }
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let stats = db.get_tag_usage_stats(since).await?;
+    let (used, unused): (Vec<_>, Vec<_>) = stats.into_iter().partition(|x| x.uses > 0);
+    let most_used = format_tag_stats(
+        used.iter().sorted_by_key(|x| std::cmp::Reverse(x.uses)).take(TAG_STATS_LIST_LENGTH),
+    );
+    let least_used =
+        format_tag_stats(used.iter().sorted_by_key(|x| x.uses).take(TAG_STATS_LIST_LENGTH));
+    let unused_list = unused
+        .iter()
+        .sorted_by_key(|x| x.last_used)
+        .map(|x| match x.last_used {
+            Some(last_used) => format!("{} (last used {})", x.tag, last_used),
+            None => format!("{} (never used)", x.tag),
+        })
+        .join(", ");
+
+    ctx.send_embed(|e| {
+        e.title(format!("Tag usage in the last {} days", days));
+        e.field("Most used", most_used, true);
+        e.field("Least used", least_used, true);
+        e.field(
+            format!("Not used in {} days ({})", days, unused.len()),
+            if unused_list.is_empty() {
+                "-".to_string()
+            } else {
+                util::ellipsis_text(&unused_list, 1024)
+            },
+            false,
+        );
+    })
+    .await?;
+    Ok(())
+}
+
+fn format_tag_stats<'a>(stats: impl Iterator<Item = &'a TagUsageStats>) -> String {
+    let list = stats.map(|x| format!("{}: {}", x.tag, x.uses)).join("\n");
+    if list.is_empty() {
+        "-".to_string()
+    } else {
+        list
+    }
+}
+
+/// Download all tags as a tarball of Markdown files
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "export"
+)]
+pub async fn tag_export(ctx: Ctx<'_>) -> Res<()> {
+    let db = ctx.get_db();
+    let tags = db.get_all_tags().await?;
+    let aliases = aliases_by_tag(db.list_tag_names().await?);
+
+    let files = tags
+        .into_iter()
+        .map(|tag| TagFile {
+            aliases: aliases.get(&tag.name.to_lowercase()).cloned().unwrap_or_default(),
+            name: tag.name,
+            category: tag.category,
+            official: tag.official,
+            templated: tag.templated,
+            author: Some(tag.moderator),
+            content: tag.content,
+            rich_content: tag.rich_content,
+        })
+        .collect_vec();
+    let archive = tag_archive::write_archive(&files)?;
+
+    let filename = format!("tags-{}.tar.gz", Utc::now().format("%Y-%m-%d"));
+    ctx.send(|m| {
+        m.content(format!("Exported {} tags", files.len()));
+        m.attachment(AttachmentType::Bytes { data: Cow::from(archive), filename })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Import tags from a tarball of Markdown files, like the ones created by `/settag export`
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "import"
+)]
+pub async fn tag_import(
+    ctx: Ctx<'_>,
+    #[description = "A .tar or .tar.gz file"] archive: Attachment,
+) -> Res<()> {
+    if archive.size > MAX_TAG_ARCHIVE_SIZE {
+        abort_with!("That file is too large");
+    }
+    ctx.defer().await?;
+
+    let data = archive.download().await?;
+    let files = match tag_archive::read_archive(&data) {
+        Ok(files) => files,
+        Err(err) => abort_with!(UserErr::Other(format!("Couldn't read the archive: {:#}", err))),
+    };
+    if files.is_empty() {
+        abort_with!("The archive doesn't contain any Markdown files");
+    }
+    for file in &files {
+        if let Err(err) = validate_tag_file(file) {
+            abort_with!(UserErr::Other(format!("Invalid tag {}: {}", file.name, err)));
+        }
+    }
+
+    let db = ctx.get_db();
+    let existing_tags: HashMap<String, Tag> =
+        db.get_all_tags().await?.into_iter().map(|x| (x.name.to_lowercase(), x)).collect();
+    let tag_names = db.list_tag_names().await?;
+    if let Err(err) = check_import_alias_conflicts(&files, &tag_names) {
+        abort_with!(UserErr::Other(err));
+    }
+    let existing_aliases = aliases_by_tag(tag_names);
+
+    let changes = files
+        .iter()
+        .map(|file| {
+            let key = file.name.to_lowercase();
+            let aliases = existing_aliases.get(&key).map(|x| x.as_slice()).unwrap_or_default();
+            (file, diff_tag_file(file, existing_tags.get(&key), aliases))
+        })
+        .collect_vec();
+
+    let created = changes.iter().filter(|(_, x)| *x == TagImportChange::Created).collect_vec();
+    let changed = changes
+        .iter()
+        .filter_map(|(file, change)| match change {
+            TagImportChange::Changed(what) => Some((file, what)),
+            _ => None,
+        })
+        .collect_vec();
+    let unchanged_count = changes.len() - created.len() - changed.len();
+
+    if created.is_empty() && changed.is_empty() {
+        abort_with!("All tags in the archive are identical to the existing ones");
+    }
+
+    let preview = embeds::make_create_embed(ctx.discord(), |e| {
+        e.title("Import tags?");
+        let created_list = created.iter().map(|(file, _)| &file.name).join(", ");
+        let changed_list = changed
+            .iter()
+            .map(|(file, what)| format!("{} ({})", file.name, what.join(", ")))
+            .join("\n");
+        if !created_list.is_empty() {
+            e.field(
+                format!("New ({})", created.len()),
+                util::ellipsis_text(&created_list, 1024),
+                false,
+            );
+        }
+        if !changed_list.is_empty() {
+            e.field(
+                format!("Changed ({})", changed.len()),
+                util::ellipsis_text(&changed_list, 1024),
+                false,
+            );
+        }
+        e.field("Unchanged", unchanged_count, false)
+    })
+    .await;
+    if !prompt_confirmation(ctx, preview).await? {
+        ctx.say_error("Cancelled the import").await?;
+        return Ok(());
+    }
+
+    for (file, change) in &changes {
+        let existing = existing_tags.get(&file.name.to_lowercase());
+        // keep the capitalization of tags that already exist
+        let tag_name = existing.map(|x| x.name.clone()).unwrap_or_else(|| file.name.clone());
+        let what = match change {
+            TagImportChange::Unchanged => continue,
+            TagImportChange::Created => None,
+            TagImportChange::Changed(what) => Some(what),
+        };
+
+        if what.map_or(true, |what| what.iter().any(|x| TAG_IMPORT_CONTENT_CHANGES.contains(x))) {
+            db.set_tag(
+                file.author.unwrap_or(ctx.author().id),
+                tag_name.clone(),
+                file.content.clone(),
+                file.rich_content.clone(),
+                file.official,
+                Some(Utc::now()),
+            )
+            .await?;
+        }
+        if existing.and_then(|x| x.category.as_ref()) != file.category.as_ref() {
+            db.set_tag_category(&tag_name, file.category.clone()).await?;
+        }
+        if existing.map_or(false, |x| x.templated) != file.templated {
+            db.set_tag_templated(&tag_name, file.templated).await?;
+        }
+    }
+    // aliases are only added once all removed ones are gone, as they may move between tags
+    for (file, _) in &changes {
+        let current_aliases =
+            existing_aliases.get(&file.name.to_lowercase()).cloned().unwrap_or_default();
+        for alias in &current_aliases {
+            if !file.aliases.iter().any(|x| x.eq_ignore_ascii_case(alias)) {
+                db.remove_tag_alias(alias).await?;
+            }
+        }
+    }
+    for (file, _) in &changes {
+        let current_aliases =
+            existing_aliases.get(&file.name.to_lowercase()).cloned().unwrap_or_default();
+        let tag_name = existing_tags
+            .get(&file.name.to_lowercase())
+            .map(|x| x.name.clone())
+            .unwrap_or_else(|| file.name.clone());
+        for alias in &file.aliases {
+            if !current_aliases.iter().any(|x| x.eq_ignore_ascii_case(alias)) {
+                db.add_tag_alias(alias.clone(), tag_name.clone()).await?;
+            }
+        }
+    }
+
+    ctx.say_success(format!("Created {} and updated {} tags", created.len(), changed.len()))
+        .await?;
+    Ok(())
+}
+
+/// What importing a tag file would do to the existing tag of the same name.
+#[derive(Debug, PartialEq, Eq)]
+enum TagImportChange {
+    Created,
+    /// Lists the parts of the tag that differ.
+    Changed(Vec<&'static str>),
+    Unchanged,
+}
+
+/// Changes that require storing a new revision of the tag.
+const TAG_IMPORT_CONTENT_CHANGES: [&str; 4] = ["content", "embed", "official", "author"];
+
+fn diff_tag_file(file: &TagFile, existing: Option<&Tag>, aliases: &[String]) -> TagImportChange {
+    let existing = match existing {
+        Some(existing) => existing,
+        None => return TagImportChange::Created,
+    };
+    let mut changes = Vec::new();
+    if existing.content.trim_end() != file.content {
+        changes.push("content");
+    }
+    if existing.rich_content != file.rich_content {
+        changes.push("embed");
+    }
+    if existing.official != file.official {
+        changes.push("official");
+    }
+    if file.author.map_or(false, |x| x != existing.moderator) {
+        changes.push("author");
+    }
+    if existing.category != file.category {
+        changes.push("category");
+    }
+    if existing.templated != file.templated {
+        changes.push("templated");
+    }
+    let old_aliases: HashSet<_> = aliases.iter().map(|x| x.to_lowercase()).collect();
+    let new_aliases: HashSet<_> = file.aliases.iter().map(|x| x.to_lowercase()).collect();
+    if old_aliases != new_aliases {
+        changes.push("aliases");
+    }
+
+    if changes.is_empty() {
+        TagImportChange::Unchanged
+    } else {
+        TagImportChange::Changed(changes)
+    }
+}
+
+/// Apply the same checks to an imported tag that apply when setting it through the modal.
+fn validate_tag_file(file: &TagFile) -> Result<(), String> {
+    tag_template::validate_template(&file.content, file.templated)?;
+    validate_tag_embed_length(&file.name, &file.content, file.rich_content.as_ref())?;
+    if let Some(rich_content) = &file.rich_content {
+        if rich_content.fields.len() > MAX_TAG_FIELDS {
+            return Err(format!("Tags can have at most {} fields", MAX_TAG_FIELDS));
+        }
+        if rich_content.buttons.len() > MAX_TAG_BUTTONS {
+            return Err(format!("Tags can have at most {} buttons", MAX_TAG_BUTTONS));
+        }
+        let urls = rich_content.image_url.iter().chain(rich_content.thumbnail_url.iter());
+        for url in urls.chain(rich_content.buttons.iter().map(|x| &x.url)) {
+            validate_tag_url(url.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Make sure the aliases in an archive don't clash with other tags or aliases.
+fn check_import_alias_conflicts(files: &[TagFile], tag_names: &TagNames) -> Result<(), String> {
+    let names: HashSet<String> = (files.iter().map(|x| x.name.to_lowercase()))
+        .chain(tag_names.tags.iter().map(|x| x.to_lowercase()))
+        .collect();
+    let mut seen_aliases = HashSet::new();
+    for file in files {
+        for alias in &file.aliases {
+            let alias_lower = alias.to_lowercase();
+            let taken_by_other_alias = tag_names.aliases.iter().any(|(other, target)| {
+                other.eq_ignore_ascii_case(alias)
+                    && !target.eq_ignore_ascii_case(&file.name)
+                    && !files.iter().any(|x| x.name.eq_ignore_ascii_case(target))
+            });
+            if names.contains(&alias_lower)
+                || taken_by_other_alias
+                || !seen_aliases.insert(alias_lower)
+            {
+                return Err(format!(
+                    "The alias {} of {} is already used by another tag",
+                    alias, file.name
+                ));
+            }
+        }
+        // aliases of tags in the archive are removed during the import, unless listed again
+        let shadows_alias = tag_names.aliases.iter().any(|(alias, target)| {
+            alias.eq_ignore_ascii_case(&file.name)
+                && !files.iter().any(|x| x.name.eq_ignore_ascii_case(target))
+        });
+        if shadows_alias {
+            return Err(format!("{} is already used as an alias of another tag", file.name));
+        }
+    }
+    Ok(())
+}
+
+/// Group the aliases by the (lowercase) name of the tag they refer to.
+fn aliases_by_tag(tag_names: TagNames) -> HashMap<String, Vec<String>> {
+    let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+    for (alias, tag) in tag_names.aliases {
+        aliases.entry(tag.to_lowercase()).or_default().push(alias);
+    }
+    aliases.values_mut().for_each(|x| x.sort());
+    aliases
+}
+
+/// Manage alternative names of tags
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "alias",
+    subcommands("tag_alias_add", "tag_alias_remove")
+)]
+pub async fn tag_alias(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Add an alias that refers to an existing tag
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "add"
+)]
+pub async fn tag_alias_add(
+    ctx: Ctx<'_>,
+    #[rename = "name"]
+    #[description = "Name of the tag"]
+    #[autocomplete = "tag_autocomplete_existing"]
+    tag_name: String,
+    #[description = "The alternative name"] alias: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let alias = alias.trim().to_string();
+    if alias.is_empty() {
+        abort_with!("The alias can't be empty");
+    }
+    if db.get_tag(&alias).await?.is_some() {
+        abort_with!("There already is a tag or alias with that name");
+    }
+    // aliases always refer to the actual tag, even if they're added to another alias
+    let tag = db.get_tag(&tag_name).await?.user_error("No tag with this name exists")?;
+    db.add_tag_alias(alias.clone(), tag.name.clone()).await?;
+    ctx.say_success(format!("{} now refers to the tag {}", alias, tag.name)).await?;
+    Ok(())
+}
+
+/// Remove an alias
+#[poise::command(
+    slash_command,
+    guild_only,
+    prefix_command,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove"
+)]
+pub async fn tag_alias_remove(
+    ctx: Ctx<'_>,
+    #[description = "The alias to remove"]
+    #[autocomplete = "tag_alias_autocomplete"]
+    alias: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    if !db.remove_tag_alias(&alias).await? {
+        abort_with!("No alias with this name exists");
+    }
+    ctx.say_success(format!("Removed the alias {}", alias)).await?;
+    Ok(())
+}
+
 #[derive(Debug, poise::Modal)]
 #[name = "Tag"]
 struct TagModal {
@@ -103,6 +933,149 @@ struct TagModal {
     content: String,
 }
 
+#[derive(Debug, poise::Modal)]
+#[name = "Tag"]
+struct RichTagModal {
+    #[name = "Content"]
+    #[placeholder = "Content of your tag. If templated, can use {user}, {channel}, {args} and {1} to {9}"]
+    #[paragraph]
+    content: String,
+    #[name = "Title"]
+    #[placeholder = "Defaults to the name of the tag"]
+    title: Option<String>,
+    #[name = "Image URL"]
+    image: Option<String>,
+    #[name = "Color"]
+    #[placeholder = "Hex color, like #458588"]
+    color: Option<String>,
+    #[name = "Fields, buttons and thumbnail"]
+    #[placeholder = "One per line: field: Name | Value, inline: Name | Value, button: Label | URL, thumbnail: URL"]
+    #[paragraph]
+    extra: Option<String>,
+}
+
+impl RichTagModal {
+    fn from_tag(tag: Option<&Tag>) -> Self {
+        let rich = tag.and_then(|x| x.rich_content.clone()).unwrap_or_default();
+        let extra = rich
+            .fields
+            .iter()
+            .map(|x| {
+                let kind = if x.inline { "inline" } else { "field" };
+                format!("{}: {} | {}", kind, x.name, x.value)
+            })
+            .chain(rich.buttons.iter().map(|x| format!("button: {} | {}", x.label, x.url)))
+            .chain(rich.thumbnail_url.iter().map(|x| format!("thumbnail: {}", x)))
+            .join("\n");
+        RichTagModal {
+            content: tag.map(|x| x.content.clone()).unwrap_or_default(),
+            title: rich.title,
+            image: rich.image_url,
+            color: rich.color.map(|x| format!("#{:06x}", x)),
+            extra: Some(extra).filter(|x| !x.is_empty()),
+        }
+    }
+
+    /// Split the modal into the text content and the rich content of a tag.
+    /// Tags that only consist of text don't get any rich content.
+    fn into_tag_content(self) -> Result<(String, Option<RichTagContent>), String> {
+        let non_empty =
+            |x: Option<String>| x.map(|x| x.trim().to_string()).filter(|x| !x.is_empty());
+        let mut rich = RichTagContent {
+            title: non_empty(self.title),
+            image_url: non_empty(self.image).map(validate_tag_url).transpose()?,
+            ..RichTagContent::default()
+        };
+        if let Some(color) = non_empty(self.color) {
+            let parsed = u32::from_str_radix(color.trim_start_matches('#'), 16)
+                .ok()
+                .filter(|x| *x <= 0xffffff)
+                .ok_or_else(|| format!("{} is not a valid hex color", color))?;
+            rich.color = Some(parsed);
+        }
+
+        for line in self.extra.iter().flat_map(|x| x.lines()).map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            let (kind, value) = line
+                .split_once(':')
+                .map(|(kind, value)| (kind.trim(), value.trim()))
+                .ok_or_else(|| format!("Couldn't understand the line `{}`", line))?;
+            match kind {
+                "field" | "inline" => {
+                    let (name, value) = value.split_once('|').ok_or_else(|| {
+                        "Fields need a name and a value, like `field: Name | Value`".to_string()
+                    })?;
+                    let (name, value) = (name.trim(), value.trim());
+                    if name.is_empty() || name.len() > 256 || value.is_empty() || value.len() > 1024
+                    {
+                        return Err(format!("The field `{}` is empty or too long", name));
+                    }
+                    rich.fields.push(RichTagField {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                        inline: kind == "inline",
+                    });
+                }
+                "button" => {
+                    let (label, url) = value.split_once('|').ok_or_else(|| {
+                        "Buttons need a label and a URL, like `button: Label | https://example.com`"
+                            .to_string()
+                    })?;
+                    let label = label.trim();
+                    if label.is_empty() || label.len() > 80 {
+                        return Err(format!("The button label `{}` is empty or too long", label));
+                    }
+                    let url = validate_tag_url(url.trim().to_string())?;
+                    rich.buttons.push(TagLinkButton { label: label.to_string(), url });
+                }
+                "thumbnail" => rich.thumbnail_url = Some(validate_tag_url(value.to_string())?),
+                _ => return Err(format!("Couldn't understand the line `{}`", line)),
+            }
+        }
+
+        if rich.fields.len() > MAX_TAG_FIELDS {
+            return Err(format!("Tags can have at most {} fields", MAX_TAG_FIELDS));
+        }
+        if rich.buttons.len() > MAX_TAG_BUTTONS {
+            return Err(format!("Tags can have at most {} buttons", MAX_TAG_BUTTONS));
+        }
+        let rich = Some(rich).filter(|x| *x != RichTagContent::default());
+        Ok((self.content, rich))
+    }
+}
+
+/// Check that the embed of a tag stays within discords limit for the total length of an embed.
+fn validate_tag_embed_length(
+    name: &str,
+    content: &str,
+    rich_content: Option<&RichTagContent>,
+) -> Result<(), String> {
+    let title = rich_content.and_then(|x| x.title.as_deref()).unwrap_or(name);
+    let fields = rich_content.map_or(&[][..], |x| x.fields.as_slice());
+    let length = title.chars().count()
+        + content.chars().count().min(4096)
+        + fields.iter().map(|x| x.name.chars().count() + x.value.chars().count()).sum::<usize>()
+        + TAG_FOOTER_RESERVED_LENGTH;
+    if length > MAX_EMBED_LENGTH {
+        return Err(format!(
+            "All the text of a tag can be at most {} characters long, this one has {}",
+            MAX_EMBED_LENGTH - TAG_FOOTER_RESERVED_LENGTH,
+            length - TAG_FOOTER_RESERVED_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+fn validate_tag_url(url: String) -> Result<String, String> {
+    if util::validate_url(&url) {
+        Ok(url)
+    } else {
+        Err(format!("{} is not a valid URL", url))
+    }
+}
+
 /// Save a new tag or update an old one.
 #[poise::command(
     slash_command,
@@ -116,39 +1089,354 @@ pub async fn tag_set(
     #[description = "The name of the tag"]
     #[autocomplete = "tag_autocomplete"]
     tag_name: String,
+    #[description = "Whether placeholders like {user} and {1} get substituted. Keeps the current setting by default"]
+    templated: Option<bool>,
 ) -> Res<()> {
     let ctx = Ctx::Application(app_ctx);
     let db = ctx.get_db();
 
     let existing_tag = db.get_tag(&tag_name).await?;
-    // Content to pre-fill into the modal text field
-    let default_content = existing_tag.map(|x| x.content).unwrap_or_default();
+    let templated =
+        templated.unwrap_or_else(|| existing_tag.as_ref().map_or(false, |x| x.templated));
+    // When editing a tag through one of its aliases, edit the actual tag
+    let tag_name = existing_tag.as_ref().map(|x| x.name.clone()).unwrap_or(tag_name);
+    // Content to pre-fill into the modal text fields
+    let defaults = RichTagModal::from_tag(existing_tag.as_ref());
 
-    let result = TagModal::execute_with_defaults(app_ctx, TagModal { content: default_content })
+    let result = RichTagModal::execute_with_defaults(app_ctx, defaults)
         .instrument(tracing::info_span!("wait for modal response"))
         .await?;
 
-    db.set_tag(ctx.author().id, tag_name, result.content, true, Some(Utc::now())).await?;
+    let (content, rich_content) = match result.into_tag_content() {
+        Ok(content) => content,
+        Err(err) => abort_with!(UserErr::Other(err)),
+    };
+    if let Err(err) = tag_template::validate_template(&content, templated) {
+        abort_with!(UserErr::Other(err));
+    }
+    if let Err(err) = validate_tag_embed_length(&tag_name, &content, rich_content.as_ref()) {
+        abort_with!(UserErr::Other(err));
+    }
+
+    let tag = db
+        .set_tag(ctx.author().id, tag_name, content, rich_content, true, Some(Utc::now()))
+        .await?;
+    if tag.templated != templated {
+        db.set_tag_templated(&tag.name, templated).await?;
+    }
     ctx.say_success("Succesfully set!").await?;
     Ok(())
 }
 
+/// Propose a new tag, which gets added once a moderator approves it
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::User }",
+    rename = "proposetag"
+)]
+pub async fn proposetag(
+    app_ctx: AppCtx<'_>,
+    #[rename = "name"]
+    #[description = "The name of the tag"]
+    tag_name: String,
+) -> Res<()> {
+    let ctx = Ctx::Application(app_ctx);
+    let (config, db) = (ctx.get_config(), ctx.get_db());
+    let review_channel = match config.channel_tag_review {
+        Some(channel) => channel,
+        None => abort_with!("Tag proposals are not enabled"),
+    };
+
+    let tag_name = tag_name.trim().to_string();
+    if tag_name.is_empty() {
+        abort_with!("The name of the tag can't be empty");
+    }
+    if db.get_tag(&tag_name).await?.is_some() {
+        abort_with!("There already is a tag with that name");
+    }
+
+    let result = TagModal::execute(app_ctx)
+        .instrument(tracing::info_span!("wait for modal response"))
+        .await?;
+
+    let proposal = db.add_tag_proposal(tag_name, result.content, ctx.author().id).await?;
+    review_channel
+        .send_message(&ctx.discord(), |m| {
+            m.set_embed(make_tag_proposal_embed(&proposal, ctx.author()));
+            m.components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.label("Approve");
+                        b.style(ButtonStyle::Success);
+                        b.custom_id(TagProposalButtonId(TagProposalAction::Approve, proposal.id))
+                    });
+                    r.create_button(|b| {
+                        b.label("Edit");
+                        b.style(ButtonStyle::Secondary);
+                        b.custom_id(TagProposalButtonId(TagProposalAction::Edit, proposal.id))
+                    });
+                    r.create_button(|b| {
+                        b.label("Reject");
+                        b.style(ButtonStyle::Danger);
+                        b.custom_id(TagProposalButtonId(TagProposalAction::Reject, proposal.id))
+                    })
+                })
+            })
+        })
+        .await?;
+
+    ctx.say_success("Thanks! Your tag will be added once a moderator approved it.").await?;
+    Ok(())
+}
+
+fn make_tag_proposal_embed(proposal: &TagProposal, author: &User) -> CreateEmbed {
+    let mut e = CreateEmbed::default();
+    e.author(|a| a.name(author.tag()).icon_url(author.face()));
+    e.title(format!("Tag proposal: {}", proposal.name));
+    e.description(util::ellipsis_text(&proposal.content, 4000));
+    e.footer(|f| f.text(format!("Proposal #{}", proposal.id)));
+    e.timestamp(proposal.create_date);
+    e
+}
+
+/// Intercept an interaction and possibly handle it being a press of a tag proposal review button.
+/// Returns Ok(true) if it _was_ a tag proposal button press, and false otherwise
+#[tracing::instrument(skip_all)]
+pub async fn handle_tag_proposal_button_clicked(
+    ctx: &client::Context,
+    interaction: &Interaction,
+) -> Res<bool> {
+    let interaction = match interaction {
+        Interaction::MessageComponent(x) if x.data.custom_id.starts_with("tagproposal-") => x,
+        _ => return Ok(false),
+    };
+    let TagProposalButtonId(action, proposal_id) = interaction.data.custom_id.parse()?;
+
+    if checks::get_permission_level(ctx, &interaction.user).await? != PermissionLevel::Mod {
+        respond_ephemeral(ctx, interaction, "Only moderators can review tag proposals").await?;
+        return Ok(true);
+    }
+
+    let db = ctx.get_db().await;
+    let proposal = match db.get_tag_proposal(proposal_id).await? {
+        Some(proposal) => proposal,
+        None => {
+            respond_ephemeral(ctx, interaction, "This proposal has already been reviewed").await?;
+            return Ok(true);
+        }
+    };
+
+    let content = match action {
+        TagProposalAction::Approve | TagProposalAction::Reject => {
+            interaction
+                .create_interaction_response(&ctx, |ir| {
+                    ir.kind(InteractionResponseType::DeferredUpdateMessage)
+                })
+                .await?;
+            proposal.content.clone()
+        }
+        TagProposalAction::Edit => {
+            let modal_id = format!("tagproposal-modal-{}", proposal.id);
+            interaction
+                .create_interaction_response(&ctx, |ir| {
+                    ir.kind(InteractionResponseType::Modal);
+                    ir.interaction_response_data(|d| {
+                        d.custom_id(&modal_id);
+                        d.title("Tag");
+                        d.components(|c| {
+                            c.create_action_row(|r| {
+                                r.create_input_text(|t| {
+                                    t.custom_id("content");
+                                    t.style(InputTextStyle::Paragraph);
+                                    t.label("Content");
+                                    t.value(&proposal.content);
+                                    t.required(true)
+                                })
+                            })
+                        })
+                    })
+                })
+                .await?;
+            let response = CollectModalInteraction::new(&ctx.shard)
+                .author_id(interaction.user.id)
+                .filter(move |x| x.data.custom_id == modal_id)
+                .timeout(std::time::Duration::from_secs(15 * 60))
+                .await;
+            let response = match response {
+                Some(response) => response,
+                None => return Ok(true),
+            };
+            // Send acknowledgement so that the pop-up is closed
+            response
+                .create_interaction_response(&ctx, |b| {
+                    b.kind(InteractionResponseType::DeferredUpdateMessage)
+                })
+                .await?;
+            response
+                .data
+                .components
+                .iter()
+                .flat_map(|x| x.components.iter())
+                .find_map(|x| match x {
+                    ActionRowComponent::InputText(text) if text.custom_id == "content" => {
+                        Some(text.value.clone())
+                    }
+                    _ => None,
+                })
+                .context("Tag proposal modal without content")?
+        }
+    };
+
+    let approved = action != TagProposalAction::Reject;
+    if approved && db.get_tag(&proposal.name).await?.is_some() {
+        interaction
+            .create_followup_message(&ctx, |m| {
+                m.content("A tag with that name has been added in the meantime").ephemeral(true)
+            })
+            .await?;
+        return Ok(true);
+    }
+    if approved {
+        db.set_tag(proposal.author, proposal.name.clone(), content, None, false, Some(Utc::now()))
+            .await?;
+    }
+    db.delete_tag_proposal(proposal.id).await?;
+
+    let mut embed = interaction
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .context("Tag proposal message without an embed")?;
+    if approved {
+        embed.field("Approved by", interaction.user.mention(), false);
+        embed.color(serenity::utils::Color::from_rgb(184, 187, 38));
+    } else {
+        embed.field("Rejected by", interaction.user.mention(), false);
+        embed.color(serenity::utils::Color::from_rgb(251, 73, 52));
+    }
+    interaction.message.clone().edit(&ctx, |m| m.set_embed(embed).components(|c| c)).await?;
+
+    let notice = if approved {
+        format!("Your proposed tag `{}` has been approved!", proposal.name)
+    } else {
+        format!("Your proposed tag `{}` has been rejected.", proposal.name)
+    };
+    // the author might not accept DMs, which is fine
+    if let Ok(dm_channel) = proposal.author.create_dm_channel(&ctx).await {
+        let _ = dm_channel.say(&ctx, notice).await;
+    }
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagProposalAction {
+    Approve,
+    Edit,
+    Reject,
+}
+
+struct TagProposalButtonId(TagProposalAction, i64);
+
+impl ToString for TagProposalButtonId {
+    fn to_string(&self) -> String {
+        let action = match self.0 {
+            TagProposalAction::Approve => "approve",
+            TagProposalAction::Edit => "edit",
+            TagProposalAction::Reject => "reject",
+        };
+        format!("tagproposal-{}-{}", action, self.1)
+    }
+}
+
+impl FromStr for TagProposalButtonId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, rest) = s.split_once('-').context("Malformed TagProposalButtonId")?;
+        let (action, id) = rest.split_once('-').context("Malformed TagProposalButtonId")?;
+        let action = match action {
+            "approve" => TagProposalAction::Approve,
+            "edit" => TagProposalAction::Edit,
+            "reject" => TagProposalAction::Reject,
+            _ => anyhow::bail!("Malformed TagProposalAction"),
+        };
+        Ok(Self(action, id.parse()?))
+    }
+}
+
 /// Autocomplete all tags, but also provide whatever the user has already typed as one of the options.
 /// Used in /tag set, to provide completion for edits, but also allow adding new tags
 async fn tag_autocomplete(ctx: Ctx<'_>, partial: &str) -> impl Iterator<Item = String> {
-    let last = if partial.is_empty() { vec![] } else { vec![partial.to_string()] };
-    tag_autocomplete_existing(ctx, partial).await.chain(last).dedup() // when the partial fully matches a value, we otherwise get a duplicate
+    let existing = tag_autocomplete_existing(ctx, partial).await.collect_vec();
+    // when the partial fully matches a value, we otherwise get a duplicate
+    let last = if partial.is_empty() || existing.iter().any(|x| x == partial) {
+        None
+    } else {
+        Some(partial.to_string())
+    };
+    existing.into_iter().chain(last)
 }
 
-/// Autocomplete all tags
-async fn tag_autocomplete_existing(ctx: Ctx<'_>, partial: &str) -> impl Iterator<Item = String> {
+/// Autocomplete all tags and their aliases, most popular first.
+/// If no name matches, tags whose content matches are suggested instead.
+pub async fn tag_autocomplete_existing(
+    ctx: Ctx<'_>,
+    partial: &str,
+) -> impl Iterator<Item = String> {
     let db = ctx.get_db();
-    let tags = match db.list_tags().await {
-        Ok(tags) => tags,
-        Err(_) => Vec::new(),
-    };
+    let tag_names = db.list_tag_names().await.unwrap_or_default();
+    let popularity = db.get_tag_popularity().await.unwrap_or_default();
+
+    let partial_lower = partial.to_ascii_lowercase();
+
+    // aliases are ranked by the popularity of the tag they refer to
+    let aliases = tag_names.aliases.into_iter();
+    let name_matches = tag_names
+        .tags
+        .into_iter()
+        .map(|tag| (tag.clone(), tag))
+        .chain(aliases)
+        .filter(|(name, _)| name.to_ascii_lowercase().starts_with(&partial_lower))
+        .map(|(name, tag)| (popularity.get(&tag.to_lowercase()).copied().unwrap_or(0), name))
+        .sorted_by(|(a_uses, a_name), (b_uses, b_name)| {
+            b_uses.cmp(a_uses).then_with(|| a_name.cmp(b_name))
+        })
+        .map(|(_, name)| name)
+        .collect_vec();
+
+    if name_matches.is_empty() && !partial.is_empty() {
+        let content_matches = db.search_tags(partial, 25).await.unwrap_or_default();
+        content_matches.into_iter().map(|x| x.name).collect_vec().into_iter()
+    } else {
+        name_matches.into_iter()
+    }
+}
+
+/// Autocomplete all aliases
+async fn tag_alias_autocomplete(ctx: Ctx<'_>, partial: &str) -> impl Iterator<Item = String> {
+    let db = ctx.get_db();
+    let tag_names = db.list_tag_names().await.unwrap_or_default();
+
+    let partial = partial.to_ascii_lowercase();
+
+    tag_names
+        .aliases
+        .into_keys()
+        .filter(move |alias| alias.to_ascii_lowercase().starts_with(&partial))
+}
+
+/// Autocomplete the categories that are already in use
+async fn tag_category_autocomplete(ctx: Ctx<'_>, partial: &str) -> impl Iterator<Item = String> {
+    let db = ctx.get_db();
+    let tags = db.list_tags_with_category(false).await.unwrap_or_default();
 
     let partial = partial.to_ascii_lowercase();
 
-    tags.into_iter().filter(move |tag| tag.to_ascii_lowercase().starts_with(&partial))
+    tags.into_iter()
+        .filter_map(|(_, category)| category)
+        .unique()
+        .filter(move |category| category.starts_with(&partial))
 }
@@ -1,16 +1,34 @@
+use chrono::Utc;
 use itertools::Itertools;
-use lazy_static::lazy_static;
 use robbb_db::{
     fetch::Fetch,
     fetch_field::{FetchField, FETCH_KEY_ORDER},
+    fetch_stats::FetchStat,
+    fetch_value_alias::FetchValueAliases,
+    Db,
 };
 
 use super::{fetch::format_fetch_field_value, *};
 use std::collections::HashMap;
 
-static EXCLUDED_FETCH_FIELDS: &[FetchField] =
+pub(crate) static EXCLUDED_FETCH_FIELDS: &[FetchField] =
     &[FetchField::Dotfiles, FetchField::Description, FetchField::Git, FetchField::Image];
 
+/// Amount of the most common values per field that get recorded in the daily fetch stats.
+const FETCH_STATS_VALUES_PER_FIELD: usize = 25;
+
+/// Words that distros put between their name and version, or after it, such as architectures.
+const DISTRO_SUFFIX_WORDS: &[&str] = &[
+    "release", "lts", "rolling", "x86_64", "amd64", "i386", "i486", "i586", "i686", "aarch64",
+    "arm64", "armv6l", "armv7l", "riscv64", "ppc64le",
+];
+
+/// Amount of months shown in `/toptrend`.
+const TREND_MONTHS: usize = 24;
+
+/// Amount of values compared in `/toptrend` when no specific value is requested.
+const TREND_VALUE_COUNT: usize = 5;
+
 /// Get statistics about what the community uses.
 #[poise::command(
     slash_command,
@@ -22,22 +40,30 @@ pub async fn top(
     ctx: Ctx<'_>,
     #[description = "What do you care about?"] field: Option<FetchField>,
     #[description = "Regex pattern for values you want more details about"] pattern: Option<String>,
+    #[description = "Also show the results as a chart"] chart: Option<ChartKind>,
+    #[description = "Only show the chart, without the text"]
+    #[flag]
+    chart_only: bool,
 ) -> Res<()> {
+    if chart == Some(ChartKind::Pie) && field.is_none() {
+        abort_with!("Pie charts are only available for a single field");
+    }
     // defer, as get_all_fetches can apparently be quite slow
     ctx.defer().await?;
     let db = ctx.get_db();
 
     let fetches = db.get_all_fetches().await?;
+    let aliases = db.get_fetch_value_aliases().await?;
 
     match (field, pattern) {
         (Some(field), Some(pattern)) => {
-            top_for_regex(ctx, fetches, field, &pattern).await?;
+            top_for_regex(ctx, fetches, field, &pattern, chart, chart_only).await?;
         }
         (Some(field), None) => {
-            top_for_field(ctx, fetches, field).await?;
+            top_for_field(ctx, &aliases, fetches, field, chart, chart_only).await?;
         }
         (None, None) => {
-            top_all_values(ctx, fetches).await?;
+            top_all_values(ctx, &aliases, fetches, chart.is_some(), chart_only).await?;
         }
         (None, Some(_)) => {
             abort_with!("You must also tell me what field to check");
@@ -52,6 +78,8 @@ async fn top_for_regex(
     fetches: Vec<Fetch>,
     field_name: FetchField,
     value_pattern: &str,
+    chart: Option<ChartKind>,
+    chart_only: bool,
 ) -> Res<()> {
     let regex = regex::RegexBuilder::new(value_pattern)
         .case_insensitive(true)
@@ -67,44 +95,49 @@ async fn top_for_regex(
 
     let percentage = (matching_value_count as f64 / total_field_values as f64) * 100f64;
 
-    ctx.send_embed(|e| {
-        e.title(format!("Stats for matching {}s", field_name));
-        e.description(indoc::formatdoc!(
-            "**Matching**: `{}`
-             **Total**: {}
-             **Percentage**: {:.2}
-            ",
-            value_pattern,
-            matching_value_count,
-            percentage,
-        ));
-    })
-    .await?;
-    Ok(())
+    let text = indoc::formatdoc!(
+        "**Matching**: `{}`
+         **Total**: {}
+         **Percentage**: {:.2}
+        ",
+        value_pattern,
+        matching_value_count,
+        percentage,
+    );
+    let chart_data = vec![
+        (value_pattern.to_string(), matching_value_count as f64),
+        ("other".to_string(), (total_field_values - matching_value_count) as f64),
+    ];
+    send_stats_embed(
+        ctx,
+        format!("Stats for matching {}s", field_name),
+        text,
+        chart,
+        chart_only,
+        "",
+        chart_data,
+    )
+    .await
 }
 
 #[tracing::instrument(skip_all, fields(%field_name))]
-async fn top_for_field(ctx: Ctx<'_>, fetches: Vec<Fetch>, field_name: FetchField) -> Res<()> {
-    let field_values = fetches
-        .into_iter()
-        .filter_map(|mut x| x.info.remove(&field_name))
-        .filter(|x| !x.is_empty() && x != "0")
-        .filter_map(|value| format_fetch_field_value(&field_name, value))
-        .map(|value| canonicalize_top_value(&value));
-
-    // only compare the first word when looking at distros
-    let field_value_counts = if field_name == FetchField::Distro {
-        field_values.filter_map(|value| value.split(' ').next().map(|x| x.to_string())).counts()
-    } else {
-        field_values.counts()
-    };
+async fn top_for_field(
+    ctx: Ctx<'_>,
+    aliases: &FetchValueAliases,
+    fetches: Vec<Fetch>,
+    field_name: FetchField,
+    chart: Option<ChartKind>,
+    chart_only: bool,
+) -> Res<()> {
+    let field_value_counts = count_field_values(aliases, &fetches, &field_name);
 
     let total_field_values: usize = field_value_counts.iter().map(|(_, n)| n).sum();
 
     let top_ten_field_value_counts =
-        field_value_counts.into_iter().sorted_by_key(|(_, cnt)| *cnt).rev().take(10);
+        field_value_counts.into_iter().sorted_by_key(|(_, cnt)| *cnt).rev().take(10).collect_vec();
 
     let top_values_text = top_ten_field_value_counts
+        .iter()
         .enumerate()
         .map(|(i, (value, count))| {
             format!(
@@ -112,21 +145,40 @@ async fn top_for_field(ctx: Ctx<'_>, fetches: Vec<Fetch>, field_name: FetchField
                 i,
                 value,
                 count,
-                (count as f64 / total_field_values as f64) * 100f64
+                (*count as f64 / total_field_values as f64) * 100f64
             )
         })
         .join("\n");
 
-    ctx.send_embed(|e| {
-        e.title(format!("Top {}", field_name));
-        e.description(top_values_text);
-    })
-    .await?;
-    Ok(())
+    let top_ten_total: usize = top_ten_field_value_counts.iter().map(|(_, n)| n).sum();
+    let mut chart_data = top_ten_field_value_counts
+        .into_iter()
+        .map(|(value, count)| (value, count as f64))
+        .collect_vec();
+    // a pie chart has to include everything else to show the actual share of the values
+    if chart == Some(ChartKind::Pie) && top_ten_total < total_field_values {
+        chart_data.push(("other".to_string(), (total_field_values - top_ten_total) as f64));
+    }
+    send_stats_embed(
+        ctx,
+        format!("Top {}", field_name),
+        top_values_text,
+        chart,
+        chart_only,
+        "",
+        chart_data,
+    )
+    .await
 }
 
 #[tracing::instrument(skip_all)]
-async fn top_all_values(ctx: Ctx<'_>, fetches: Vec<Fetch>) -> Res<()> {
+async fn top_all_values(
+    ctx: Ctx<'_>,
+    aliases: &FetchValueAliases,
+    fetches: Vec<Fetch>,
+    show_chart: bool,
+    chart_only: bool,
+) -> Res<()> {
     let mut data: HashMap<FetchField, Vec<String>> = HashMap::new();
     for fetch in fetches {
         for field_name in FETCH_KEY_ORDER.iter().filter(|&x| !EXCLUDED_FETCH_FIELDS.contains(x)) {
@@ -136,55 +188,277 @@ async fn top_all_values(ctx: Ctx<'_>, fetches: Vec<Fetch>) -> Res<()> {
             }
         }
     }
-    let maxes = data.into_iter().filter_map(|(field_name, values)| {
-        let values = values.into_iter().filter(|x| !x.is_empty() && "0" != x);
+    let maxes = data
+        .into_iter()
+        .filter_map(|(field_name, values)| {
+            let values = values.into_iter().filter(|x| !x.is_empty() && "0" != x);
 
-        let (most_popular_value, most_popular_cnt) = values
-            .clone()
-            .map(|value| canonicalize_top_value(&value))
-            .counts()
-            .into_iter()
-            .max_by_key(|(_, cnt)| *cnt)?;
+            let (most_popular_value, most_popular_cnt) = values
+                .clone()
+                .map(|value| canonicalize_field_value(aliases, &field_name, &value))
+                .counts()
+                .into_iter()
+                .max_by_key(|(_, cnt)| *cnt)?;
 
-        let most_popular_value = format_fetch_field_value(&field_name, most_popular_value)?;
+            let most_popular_value = format_fetch_field_value(&field_name, most_popular_value)?;
 
-        Some((
-            field_name,
-            most_popular_value,
-            most_popular_cnt,
-            ((most_popular_cnt as f64 / values.count() as f64) * 100f64),
-        ))
-    });
+            Some((
+                field_name,
+                most_popular_value,
+                most_popular_cnt,
+                ((most_popular_cnt as f64 / values.count() as f64) * 100f64),
+            ))
+        })
+        .collect_vec();
 
     let top_values_text = maxes
+        .iter()
         .map(|(field, value, _cnt, perc)| format!("**{}**: {} ({:.2}%)", field, value, perc))
         .join("\n");
+    let chart_data = maxes
+        .into_iter()
+        .map(|(field, value, _cnt, perc)| (format!("{}: {}", field, value), perc))
+        .collect_vec();
+
+    // the values of different fields aren't parts of one whole, so they can't be shown as a pie
+    let chart = show_chart.then_some(ChartKind::Bar);
+    send_stats_embed(ctx, "Top".to_string(), top_values_text, chart, chart_only, "%", chart_data)
+        .await
+}
+
+/// Count how many users use each value of a field, merging equivalent values.
+fn count_field_values(
+    aliases: &FetchValueAliases,
+    fetches: &[Fetch],
+    field_name: &FetchField,
+) -> HashMap<String, usize> {
+    fetches
+        .iter()
+        .filter_map(|x| x.info.get(field_name).cloned())
+        .filter(|x| !x.is_empty() && x != "0")
+        .filter_map(|value| format_fetch_field_value(field_name, value))
+        .map(|value| canonicalize_field_value(aliases, field_name, &value))
+        .counts()
+}
+
+/// Canonicalize a value of a field using the aliases managed through `/topalias`,
+/// such that equivalent values are counted together.
+fn canonicalize_field_value(
+    aliases: &FetchValueAliases,
+    field_name: &FetchField,
+    value: &str,
+) -> String {
+    let value = value.trim();
+    if let Some(canonical) = aliases.canonicalize(field_name, value) {
+        return canonical.to_lowercase();
+    }
+    let value = if *field_name == FetchField::Distro { distro_name(value) } else { value };
+    aliases.canonicalize(field_name, value).unwrap_or(value).to_lowercase()
+}
+
+/// Strip the version, release name and architecture that distros report after their name,
+/// e.g. "Debian GNU/Linux 12 (bookworm)" becomes "Debian GNU/Linux".
+fn distro_name(value: &str) -> &str {
+    let is_suffix = |word: &str| {
+        word.starts_with(|c: char| c.is_ascii_digit() || c == '(')
+            || DISTRO_SUFFIX_WORDS.iter().any(|x| word.eq_ignore_ascii_case(x))
+    };
+    let mut name_end = 0;
+    let mut offset = 0;
+    for word in value.split(' ') {
+        if name_end > 0 && is_suffix(word) {
+            break;
+        }
+        offset += word.len() + 1;
+        if !word.is_empty() {
+            name_end = offset - 1;
+        }
+    }
+    &value[..name_end]
+}
+
+/// Record how often the most common values of each field are used today,
+/// unless that already happened.
+#[tracing::instrument(skip_all)]
+pub async fn record_fetch_stats(db: &Db) -> anyhow::Result<()> {
+    let today = Utc::now().naive_utc().date();
+    if db.get_latest_fetch_stats_day().await?.map_or(false, |day| day >= today) {
+        return Ok(());
+    }
+    let fetches = db.get_all_fetches().await?;
+    let aliases = db.get_fetch_value_aliases().await?;
+    let mut stats = Vec::new();
+    for field_name in FETCH_KEY_ORDER.iter().filter(|&x| !EXCLUDED_FETCH_FIELDS.contains(x)) {
+        let counts = count_field_values(&aliases, &fetches, field_name);
+        let total = counts.values().sum::<usize>() as i64;
+        let field_stats = counts
+            .into_iter()
+            .sorted_by_key(|(_, count)| *count)
+            .rev()
+            .take(FETCH_STATS_VALUES_PER_FIELD)
+            .map(|(value, count)| {
+                (field_name.clone(), FetchStat { day: today, value, count: count as i64, total })
+            });
+        stats.extend(field_stats);
+    }
+    db.add_fetch_stats(stats).await?;
+    Ok(())
+}
+
+/// See how the popularity of setups changed over time.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::User }",
+    rename = "toptrend"
+)]
+pub async fn top_trend(
+    ctx: Ctx<'_>,
+    #[description = "What do you care about?"] field: FetchField,
+    #[description = "The value to follow. Defaults to the currently most popular values"]
+    value: Option<String>,
+) -> Res<()> {
+    if EXCLUDED_FETCH_FIELDS.contains(&field) {
+        abort_with!("There are no stats for that field");
+    }
+    let db = ctx.get_db();
+    let stats = db.get_monthly_fetch_stats(&field).await?;
+    let months = stats
+        .into_iter()
+        .group_by(|x| x.day)
+        .into_iter()
+        .map(|(day, stats)| (day, stats.collect_vec()))
+        .collect_vec();
+    let months = &months[months.len().saturating_sub(TREND_MONTHS)..];
+    let (_, latest_stats) = months.last().user_error("There are no stats for that field yet")?;
+
+    let (title, lines) = match value {
+        Some(value) => {
+            let aliases = db.get_fetch_value_aliases().await?;
+            let value = canonicalize_field_value(&aliases, &field, &value);
+            if !months.iter().any(|(_, stats)| stats.iter().any(|x| x.value == value)) {
+                abort_with!("That value was never among the most popular ones");
+            }
+            let lines = months
+                .iter()
+                .map(|(day, stats)| {
+                    let total = stats.first().map_or(0, |x| x.total);
+                    let count = stats.iter().find(|x| x.value == value).map_or(0, |x| x.count);
+                    format!(
+                        "**{}**: {:.2}% ({} of {})",
+                        day.format("%Y-%m"),
+                        percentage(count, total),
+                        count,
+                        total
+                    )
+                })
+                .join("\n");
+            (format!("Trend of {} for {}", value, field), lines)
+        }
+        None => {
+            let values =
+                latest_stats.iter().take(TREND_VALUE_COUNT).map(|x| &x.value).collect_vec();
+            let lines = months
+                .iter()
+                .map(|(day, stats)| {
+                    let total = stats.first().map_or(0, |x| x.total);
+                    let shares = values
+                        .iter()
+                        .map(|&value| {
+                            let count =
+                                stats.iter().find(|x| &x.value == value).map_or(0, |x| x.count);
+                            format!("{} {:.1}%", value, percentage(count, total))
+                        })
+                        .join(", ");
+                    format!("**{}**: {}", day.format("%Y-%m"), shares)
+                })
+                .join("\n");
+            (format!("{} trend", field), lines)
+        }
+    };
 
     ctx.send_embed(|e| {
-        e.title("Top");
-        e.description(top_values_text);
+        e.title(title);
+        e.description(util::ellipsis_text(&lines, 4000));
+        e.footer(|f| {
+            f.text(format!(
+                "Only the {} most popular values are recorded every day",
+                FETCH_STATS_VALUES_PER_FIELD
+            ))
+        });
     })
     .await?;
     Ok(())
 }
 
-/// Given some value that has a canonical value, return that value.
-/// I.e.: "nvim" => "neovim".
-fn canonicalize_top_value(value: &str) -> String {
-    let value = value.trim().to_lowercase();
-    EQUIVALENT_VALUES
-        .iter()
-        .find(|values| values.contains(&value.as_str()))
-        .and_then(|x| x.first())
-        .map(|x| x.to_string())
-        .unwrap_or(value)
+fn percentage(count: i64, total: i64) -> f64 {
+    if total == 0 {
+        0f64
+    } else {
+        (count as f64 / total as f64) * 100f64
+    }
 }
 
-lazy_static! {
-    static ref EQUIVALENT_VALUES: Vec<Vec<&'static str>> = vec![
-        vec!["neovim", "nvim"],
-        vec!["x11", "xorg"],
-        vec!["arch linux", "arch"],
-        vec!["visual studio code", "code", "vscode"]
-    ];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robbb_db::fetch_value_alias::FetchValueAlias;
+
+    fn distro_aliases() -> FetchValueAliases {
+        let mut aliases = FetchValueAliases::default();
+        for (alias, canonical) in [
+            ("fedora linux", "fedora"),
+            ("debian gnu/linux", "debian"),
+            ("archlinux", "arch linux"),
+            ("gentoo base system", "gentoo linux"),
+            ("ubuntu 22.04 lts", "ubuntu jammy"),
+        ] {
+            aliases.insert(FetchValueAlias {
+                field: FetchField::Distro,
+                alias: alias.to_string(),
+                canonical: canonical.to_string(),
+                added_by: None,
+            });
+        }
+        aliases
+    }
+
+    #[test]
+    fn test_distro_name() {
+        assert_eq!(distro_name("Fedora Linux 39 (Workstation Edition)"), "Fedora Linux");
+        assert_eq!(distro_name("Debian GNU/Linux 12 (bookworm)"), "Debian GNU/Linux");
+        assert_eq!(distro_name("Arch Linux x86_64"), "Arch Linux");
+        assert_eq!(distro_name("Ubuntu 22.04.3 LTS x86_64"), "Ubuntu");
+        assert_eq!(distro_name("Linux Mint 21.2 (Victoria)"), "Linux Mint");
+        assert_eq!(distro_name("Gentoo Base System release 2.14"), "Gentoo Base System");
+        assert_eq!(distro_name("openSUSE Tumbleweed"), "openSUSE Tumbleweed");
+        assert_eq!(distro_name("Pop!_OS"), "Pop!_OS");
+        // the name itself is never stripped away
+        assert_eq!(distro_name("9front"), "9front");
+    }
+
+    #[test]
+    fn test_canonicalize_distro() {
+        let aliases = distro_aliases();
+        let canonicalize = |value| canonicalize_field_value(&aliases, &FetchField::Distro, value);
+        assert_eq!(canonicalize("Fedora Linux 39 (Workstation Edition)"), "fedora");
+        assert_eq!(canonicalize("Fedora Linux 38 (KDE Plasma)"), "fedora");
+        assert_eq!(canonicalize("Fedora"), "fedora");
+        assert_eq!(canonicalize("Debian GNU/Linux 12 (bookworm)"), "debian");
+        assert_eq!(canonicalize("Debian GNU/Linux 11 (bullseye) x86_64"), "debian");
+        assert_eq!(canonicalize("Arch Linux x86_64"), "arch linux");
+        assert_eq!(canonicalize("archlinux"), "arch linux");
+        assert_eq!(canonicalize("Gentoo Base System release 2.14"), "gentoo linux");
+        assert_eq!(canonicalize("Void Linux x86_64"), "void linux");
+        // aliases of the full value take precedence over stripping the version
+        assert_eq!(canonicalize("Ubuntu 22.04 LTS"), "ubuntu jammy");
+        assert_eq!(canonicalize("Ubuntu 23.10"), "ubuntu");
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_other_fields_whole() {
+        let aliases = distro_aliases();
+        let value = canonicalize_field_value(&aliases, &FetchField::Kernel, "Linux 6.5.6 x86_64");
+        assert_eq!(value, "linux 6.5.6 x86_64");
+    }
 }
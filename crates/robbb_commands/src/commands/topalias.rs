@@ -0,0 +1,135 @@
+use robbb_db::fetch_field::FetchField;
+use robbb_util::embeds;
+
+use super::top::EXCLUDED_FETCH_FIELDS;
+use super::*;
+
+/// Maximum length of an alias or canonical value.
+const MAX_FETCH_VALUE_ALIAS_LENGTH: usize = 100;
+
+/// Manage which fetch values are treated as the same value in /top and /fetch.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("topalias_add", "topalias_remove", "topalias_list")
+)]
+pub async fn topalias(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Treat a value of a fetch field as another, canonical value
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "add"
+)]
+pub async fn topalias_add(
+    ctx: Ctx<'_>,
+    #[description = "The field the alias applies to"] field: FetchField,
+    #[description = "The alternative value, i.e.: nvim"] alias: String,
+    #[description = "The value it stands for, i.e.: neovim"] canonical: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let (alias, canonical) = (alias.trim(), canonical.trim());
+    if EXCLUDED_FETCH_FIELDS.contains(&field) {
+        abort_with!("That field isn't included in /top");
+    }
+    if alias.is_empty() || canonical.is_empty() {
+        abort_with!("Neither the alias nor the canonical value may be empty");
+    }
+    if alias.len() > MAX_FETCH_VALUE_ALIAS_LENGTH || canonical.len() > MAX_FETCH_VALUE_ALIAS_LENGTH
+    {
+        abort_with!(UserErr::Other(format!(
+            "Aliases and canonical values can be at most {} characters long",
+            MAX_FETCH_VALUE_ALIAS_LENGTH
+        )));
+    }
+
+    // aliases are only resolved once, so they must not form chains
+    let aliases = db.get_fetch_value_aliases().await?;
+    if let Some(other) = aliases.canonicalize(&field, canonical) {
+        if !other.eq_ignore_ascii_case(canonical) {
+            abort_with!(UserErr::Other(format!(
+                "{} is itself an alias of {}, use that instead",
+                canonical, other
+            )));
+        }
+    }
+    if !alias.eq_ignore_ascii_case(canonical) && aliases.is_canonical(&field, alias) {
+        abort_with!(UserErr::Other(format!(
+            "Other values are aliases of {}, so it can't become an alias itself",
+            alias
+        )));
+    }
+
+    db.add_fetch_value_alias(field.clone(), alias, canonical, ctx.author().id).await?;
+    ctx.say_success(format!("{} values of `{}` now count as `{}`", field, alias, canonical))
+        .await?;
+    Ok(())
+}
+
+/// Remove an alias of a fetch value
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "remove"
+)]
+pub async fn topalias_remove(
+    ctx: Ctx<'_>,
+    #[description = "The field the alias applies to"] field: FetchField,
+    #[description = "The alias to remove"] alias: String,
+) -> Res<()> {
+    let db = ctx.get_db();
+    if !db.remove_fetch_value_alias(&field, alias.trim()).await? {
+        abort_with!("There is no such alias");
+    }
+    ctx.say_success(format!("Removed the alias `{}` of {}", alias.trim(), field)).await?;
+    Ok(())
+}
+
+/// List the aliases of fetch values
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "list"
+)]
+pub async fn topalias_list(
+    ctx: Ctx<'_>,
+    #[description = "Only list the aliases of this field"] field: Option<FetchField>,
+) -> Res<()> {
+    let db = ctx.get_db();
+    let aliases = db.get_fetch_value_aliases().await?;
+    let aliases = aliases
+        .all()
+        .into_iter()
+        .filter(|x| field.as_ref().map_or(true, |field| &x.field == field))
+        .collect_vec();
+    if aliases.is_empty() {
+        abort_with!("There are no aliases");
+    }
+
+    let fields = aliases
+        .into_iter()
+        .group_by(|x| (x.field.clone(), x.canonical.to_lowercase()))
+        .into_iter()
+        .map(|((field, _), aliases)| {
+            let aliases = aliases.collect_vec();
+            let canonical = aliases.first().map_or("", |x| x.canonical.as_str());
+            let alias_list = aliases.iter().map(|x| format!("`{}`", x.alias)).join(", ");
+            (format!("{}: {}", field, canonical), util::ellipsis_text(&alias_list, 1024))
+        })
+        .collect_vec();
+    embeds::PaginatedEmbed::create_from_fields(
+        "Fetch value aliases".to_string(),
+        fields,
+        embeds::make_create_embed(ctx.discord(), |e| e).await,
+    )
+    .await
+    .reply_to(ctx, false)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use chrono::Utc;
+use poise::serenity_prelude::{
+    application::component::{ActionRowComponent, ButtonStyle, InputTextStyle},
+    interaction::{Interaction, InteractionResponseType},
+    CollectModalInteraction, CreateEmbed, ReactionType, User,
+};
+use robbb_db::raid_mode::RaidModeAction;
+use robbb_util::log_error;
+use serenity::client;
+
+use super::*;
+
+const VERIFY_BUTTON_ID: &str = "verification-verify";
+const ANSWER_MODAL_ID: &str = "verification-answer";
+
+/// How long we wait for a member to submit the verification question modal.
+const ANSWER_TIMEOUT_SECS: u64 = 5 * 60;
+
+/// Manage the verification gate for new members.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    subcommands("verification_setup")
+)]
+pub async fn verification(_ctx: Ctx<'_>) -> Res<()> {
+    Ok(())
+}
+
+/// Post the verification button in the verification channel.
+#[poise::command(
+    slash_command,
+    guild_only,
+    custom_data = "CmdMeta { perms: PermissionLevel::Mod }",
+    rename = "setup"
+)]
+pub async fn verification_setup(ctx: Ctx<'_>) -> Res<()> {
+    let config = ctx.get_config();
+    let channel = match config.channel_verification {
+        Some(channel) if config.verification_enabled() => channel,
+        _ => abort_with!("Verification is not configured"),
+    };
+
+    channel
+        .send_message(&ctx.discord(), |m| {
+            m.embed(|e| {
+                e.title("Welcome!");
+                e.description(
+                    "Read the rules, then press the button below to get access to the server.",
+                );
+                e.color(serenity::utils::Color::from_rgb(184, 187, 38))
+            });
+            m.components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.label("Verify");
+                        b.style(ButtonStyle::Success);
+                        b.emoji(ReactionType::Unicode("✅".to_string()));
+                        b.custom_id(VERIFY_BUTTON_ID)
+                    })
+                })
+            })
+        })
+        .await?;
+    ctx.say_success(format!("Posted the verification button in {}", channel.mention())).await?;
+    Ok(())
+}
+
+/// Put a new member into the verification gate, giving them the quarantine role.
+#[tracing::instrument(skip_all, fields(member.tag = %member.user.tag()))]
+pub async fn start_verification(ctx: &client::Context, member: &Member) -> anyhow::Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let role_quarantine = match config.role_quarantine {
+        Some(role) if config.verification_enabled() => role,
+        _ => return Ok(()),
+    };
+
+    let join_time = member.joined_at.map_or_else(Utc::now, |x| *x);
+    let deadline = join_time + chrono::Duration::from_std(config.verification_timeout)?;
+    db.add_pending_verification(member.user.id, join_time, deadline).await?;
+    member.clone().add_role(&ctx, role_quarantine).await?;
+
+    log_verification_step(ctx, &member.user, "Verification started", |e| {
+        e.field("Deadline", util::format_date_detailed(deadline), false);
+    })
+    .await;
+    Ok(())
+}
+
+/// Intercept an interaction and possibly handle it being a press of the verification button.
+/// Returns Ok(true) if it _was_ a verification button press, and false otherwise
+#[tracing::instrument(skip_all)]
+pub async fn handle_verification_button_clicked(
+    ctx: &client::Context,
+    interaction: &Interaction,
+) -> Res<bool> {
+    let interaction = match interaction {
+        Interaction::MessageComponent(x) if x.data.custom_id == VERIFY_BUTTON_ID => x,
+        _ => return Ok(false),
+    };
+    let (config, db) = ctx.get_config_and_db().await;
+
+    if db.get_pending_verification(interaction.user.id).await?.is_none() {
+        respond_ephemeral(ctx, interaction, "You're already verified!").await?;
+        return Ok(true);
+    }
+
+    let raid_mode = db.get_active_raid_mode().await?;
+    if raid_mode.map_or(false, |x| x.action == RaidModeAction::Quarantine) {
+        respond_ephemeral(
+            ctx,
+            interaction,
+            "The server is currently locked down because of a raid. Please try again later.",
+        )
+        .await?;
+        return Ok(true);
+    }
+
+    let account_age = Utc::now() - *interaction.user.created_at();
+    let question = config.verification_question.as_ref().filter(|_| {
+        account_age < config.verification_young_account_age
+            && !config.verification_answers.is_empty()
+    });
+
+    let question = match question {
+        Some(question) => question,
+        None => {
+            complete_verification(ctx, interaction.user.id).await?;
+            respond_ephemeral(ctx, interaction, "Welcome to the server!").await?;
+            return Ok(true);
+        }
+    };
+
+    interaction
+        .create_interaction_response(&ctx, |ir| {
+            ir.kind(InteractionResponseType::Modal);
+            ir.interaction_response_data(|d| {
+                d.custom_id(ANSWER_MODAL_ID);
+                d.title("Verification");
+                d.components(|c| {
+                    c.create_action_row(|r| {
+                        r.create_input_text(|t| {
+                            t.custom_id("answer");
+                            t.style(InputTextStyle::Short);
+                            t.label(util::ellipsis_text(question, 45));
+                            t.placeholder(util::ellipsis_text(question, 100));
+                            t.required(true)
+                        })
+                    })
+                })
+            })
+        })
+        .await?;
+
+    let response = CollectModalInteraction::new(&ctx.shard)
+        .author_id(interaction.user.id)
+        .filter(|x| x.data.custom_id == ANSWER_MODAL_ID)
+        .timeout(std::time::Duration::from_secs(ANSWER_TIMEOUT_SECS))
+        .await;
+    // if they close the modal, they can just press the button again until their deadline is over
+    let response = match response {
+        Some(response) => response,
+        None => return Ok(true),
+    };
+
+    let answer = response
+        .data
+        .components
+        .iter()
+        .flat_map(|x| x.components.iter())
+        .find_map(|x| match x {
+            ActionRowComponent::InputText(text) if text.custom_id == "answer" => {
+                Some(text.value.trim().to_lowercase())
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    if config.verification_answers.contains(&answer) {
+        complete_verification(ctx, interaction.user.id).await?;
+        response
+            .create_interaction_response(&ctx, |ir| {
+                ir.kind(InteractionResponseType::ChannelMessageWithSource);
+                ir.interaction_response_data(|d| {
+                    d.content("Welcome to the server!").ephemeral(true)
+                })
+            })
+            .await?;
+    } else {
+        response
+            .create_interaction_response(&ctx, |ir| {
+                ir.kind(InteractionResponseType::ChannelMessageWithSource);
+                ir.interaction_response_data(|d| {
+                    d.content("That's not the right answer, sorry.").ephemeral(true)
+                })
+            })
+            .await?;
+        log_verification_step(ctx, &interaction.user, "Verification question failed", |e| {
+            e.field("Answer", util::ellipsis_text(&answer, 1024), false);
+        })
+        .await;
+        kick_unverified_member(ctx, interaction.user.id, "Failed the verification question")
+            .await?;
+    }
+    Ok(true)
+}
+
+/// Swap the quarantine role for full access.
+async fn complete_verification(ctx: &client::Context, user_id: UserId) -> anyhow::Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let mut member = config.guild.member(&ctx, user_id).await?;
+    if let Some(role_verified) = config.role_verified {
+        member.add_role(&ctx, role_verified).await?;
+    }
+    if let Some(role_quarantine) = config.role_quarantine {
+        member.remove_role(&ctx, role_quarantine).await?;
+    }
+    db.remove_pending_verification(user_id).await?;
+
+    log_verification_step(ctx, &member.user, "Verification passed", |_| {}).await;
+    Ok(())
+}
+
+/// Kick a member that failed or didn't complete the verification.
+/// The pending verification is only removed once the member is gone,
+/// so failed kicks get retried.
+pub async fn kick_unverified_member(
+    ctx: &client::Context,
+    user_id: UserId,
+    reason: &str,
+) -> anyhow::Result<()> {
+    let (config, db) = ctx.get_config_and_db().await;
+    let member = match config.guild.member(&ctx, user_id).await {
+        Ok(member) => member,
+        // they already left, so there's nobody to kick anymore
+        Err(err) if util::is_not_found(&err) => {
+            db.remove_pending_verification(user_id).await?;
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let _ = member
+        .user
+        .dm(&ctx, |m| {
+            m.content(format!(
+                "You have been removed from the server because you didn't pass verification ({}). Feel free to join again.",
+                reason
+            ))
+        })
+        .await;
+    member.kick_with_reason(&ctx, reason).await?;
+    db.remove_pending_verification(user_id).await?;
+
+    log_verification_step(ctx, &member.user, "Kicked unverified member", |e| {
+        e.field("Reason", reason, false);
+    })
+    .await;
+    Ok(())
+}
+
+async fn log_verification_step<F>(ctx: &client::Context, user: &User, title: &str, build: F)
+where
+    F: FnOnce(&mut CreateEmbed) + Send + Sync,
+{
+    let config = ctx.get_config().await;
+    let result = config
+        .channel_bot_traffic
+        .send_embed(ctx, |e| {
+            e.author(|a| a.name(title).icon_url(user.face()));
+            e.title(user.name_with_disc_and_id());
+            build(e);
+        })
+        .await;
+    log_error!(result);
+}
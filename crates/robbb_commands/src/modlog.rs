@@ -1,6 +1,6 @@
 use itertools::Itertools;
-use poise::serenity_prelude::Message;
-use robbb_db::db::mute::Mute;
+use poise::serenity_prelude::{ChannelId, Message, UserId};
+use robbb_db::db::{lockdown::Lockdown, mute::Mute, raid_mode::RaidMode};
 use robbb_util::{
     extensions::{ClientContextExt, CreateEmbedExt, MessageExt, PoiseContextExt, UserExt},
     prelude::Ctx,
@@ -164,3 +164,99 @@ pub async fn log_user_mute_ended(ctx: &client::Context, mute: &Mute) {
         })
         .await;
 }
+
+pub async fn log_raid_mode_started(ctx: &client::Context, raid_mode: &RaidMode) {
+    let config = ctx.get_config().await;
+    config
+        .log_automod_action(ctx, |e| {
+            e.title("Raid mode enabled");
+            e.description(format!(
+                "Raid mode was enabled by {}. New members will be handled with: {}",
+                raid_mode.moderator.mention(),
+                raid_mode.action,
+            ));
+            e.field("Until", util::format_date_detailed(raid_mode.end_time), false);
+        })
+        .await;
+}
+
+pub async fn log_raid_mode_ended(ctx: &client::Context, raid_mode: &RaidMode, released: &[UserId]) {
+    let config = ctx.get_config().await;
+    config
+        .log_automod_action(ctx, |e| {
+            e.title("Raid mode ended");
+            e.description(format!(
+                "Raid mode has ended after {}",
+                util::format_date_before_plaintext(raid_mode.start_time, chrono::Utc::now())
+                    .replace(" ago", "")
+            ));
+            if !released.is_empty() {
+                e.field(
+                    "Released from quarantine",
+                    util::ellipsis_text(&released.iter().map(|x| x.mention()).join(", "), 1024),
+                    false,
+                );
+            }
+        })
+        .await;
+}
+
+pub async fn log_lockdown(
+    ctx: Ctx<'_>,
+    channels: &[ChannelId],
+    failed: &[ChannelId],
+    end_time: Option<chrono::DateTime<chrono::Utc>>,
+    reason: Option<&str>,
+) {
+    let config = ctx.get_config();
+    config
+        .log_bot_action(ctx.discord(), |e| {
+            e.title("Lockdown");
+            e.author_user(ctx.author());
+            e.description(format!(
+                "{} locked {}",
+                ctx.author().id.mention(),
+                channels.iter().map(|x| x.mention()).join(", ")
+            ));
+            if let Some(end_time) = end_time {
+                e.field("Until", util::format_date_detailed(end_time), false);
+            }
+            if let Some(reason) = reason {
+                e.field("Reason", reason, false);
+            }
+            if !failed.is_empty() {
+                e.field("Failed to lock", failed.iter().map(|x| x.mention()).join(", "), false);
+            }
+        })
+        .await;
+}
+
+pub async fn log_unlock(ctx: Ctx<'_>, channels: &[ChannelId]) {
+    let config = ctx.get_config();
+    config
+        .log_bot_action(ctx.discord(), |e| {
+            e.title("Unlock");
+            e.author_user(ctx.author());
+            e.description(format!(
+                "{} unlocked {}",
+                ctx.author().id.mention(),
+                channels.iter().map(|x| x.mention()).join(", ")
+            ));
+        })
+        .await;
+}
+
+pub async fn log_lockdown_ended(ctx: &client::Context, lockdown: &Lockdown) {
+    let config = ctx.get_config().await;
+    config
+        .log_bot_action(ctx, |e| {
+            e.title("Lockdown ended");
+            e.description(format!(
+                "{} was automatically unlocked after {}",
+                lockdown.channel_id.mention(),
+                util::format_date_before_plaintext(lockdown.start_time, chrono::Utc::now())
+                    .replace(" ago", "")
+            ));
+        })
+        .await;
+}
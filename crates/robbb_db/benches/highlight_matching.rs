@@ -0,0 +1,77 @@
+//! Compares the Aho-Corasick based matching of literal highlight triggers
+//! against the previous approach of combining all triggers into a single regex.
+//! Both are given the same normalized triggers and messages, and only do the matching itself.
+//!
+//! Run with `cargo bench -p robbb_db`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use regex::{Regex, RegexBuilder};
+use robbb_db::highlights::LiteralMatcher;
+
+const TRIGGER_COUNTS: &[usize] = &[100, 1_000, 5_000];
+
+const SHORT_MESSAGE: &str = "hey, does anyone know why my polybar config isn't loading?";
+
+/// The approach used before switching to Aho-Corasick.
+fn combined_regex(triggers: &[String]) -> Regex {
+    let joined = triggers.iter().map(|x| regex::escape(x)).join("|");
+    RegexBuilder::new(&format!(r"\b(?:{})\b", joined)).case_insensitive(true).build().unwrap()
+}
+
+fn make_triggers(count: usize) -> Vec<String> {
+    (0..count).map(|x| format!("trigger{}", x)).collect()
+}
+
+/// Run the given function repeatedly for about a second, returning the average time per run.
+fn measure(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0u32;
+    while start.elapsed() < Duration::from_secs(1) || runs < 3 {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn report(name: &str, regex_time: Duration, aho_corasick_time: Duration) {
+    println!(
+        "{:<40} regex: {:>12?}  aho-corasick: {:>12?}  ({:.1}x)",
+        name,
+        regex_time,
+        aho_corasick_time,
+        regex_time.as_secs_f64() / aho_corasick_time.as_secs_f64()
+    );
+}
+
+fn main() {
+    let short_message = SHORT_MESSAGE.to_lowercase();
+    let long_message = short_message.repeat(30) + " trigger42";
+
+    for &count in TRIGGER_COUNTS {
+        let triggers = make_triggers(count);
+
+        let regex_time = measure(|| {
+            black_box(combined_regex(&triggers));
+        });
+        let aho_corasick_time = measure(|| {
+            black_box(LiteralMatcher::new(triggers.iter().map(|x| x.as_str())));
+        });
+        report(&format!("build ({} triggers)", count), regex_time, aho_corasick_time);
+
+        let regex = combined_regex(&triggers);
+        let matcher = LiteralMatcher::new(triggers.iter().map(|x| x.as_str()));
+        for (name, message) in [("short message", &short_message), ("long message", &long_message)]
+        {
+            let regex_time = measure(|| {
+                black_box(regex.find_iter(message).count());
+            });
+            let aho_corasick_time = measure(|| {
+                black_box(matcher.find_triggers(message).count());
+            });
+            report(&format!("match {} ({} triggers)", name, count), regex_time, aho_corasick_time);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS raid_mode (
+    id integer primary key asc,
+    moderator integer not null,
+    action integer not null,
+    previous_verification_level integer not null,
+    start_time datetime not null,
+    end_time datetime not null,
+    active boolean not null
+);
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS lockdown (
+    id integer primary key asc,
+    channel_id integer not null,
+    moderator integer not null,
+    reason text,
+    start_time datetime not null,
+    end_time datetime,
+    active boolean not null
+);
+
+-- The permission overwrites of a locked channel as they were before the lockdown.
+-- allow and deny are null if the channel had no overwrite for the role.
+CREATE TABLE IF NOT EXISTS lockdown_overwrite (
+    lockdown integer not null,
+    role_id integer not null,
+    allow integer,
+    deny integer,
+    PRIMARY KEY(lockdown, role_id),
+    FOREIGN KEY(lockdown) REFERENCES lockdown(id)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS auto_slowmode (
+    channel_id integer primary key,
+    threshold integer not null,
+    max_slowmode integer not null,
+    added_by integer not null
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS link_filter_domain (
+    domain text primary key,
+    allowed boolean not null,
+    added_by integer not null
+);
+
+CREATE TABLE IF NOT EXISTS link_filter_invite_guild (
+    guild_id integer primary key,
+    added_by integer not null
+);
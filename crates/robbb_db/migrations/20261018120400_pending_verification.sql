@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS pending_verification (
+    usr integer primary key,
+    join_time datetime not null,
+    deadline datetime not null
+);
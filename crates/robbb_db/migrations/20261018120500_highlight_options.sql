@@ -0,0 +1,4 @@
+ALTER TABLE highlights ADD COLUMN is_regex boolean not null default false;
+-- comma separated channel or category ids
+ALTER TABLE highlights ADD COLUMN include_channels text not null default '';
+ALTER TABLE highlights ADD COLUMN exclude_channels text not null default '';
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS highlight_ignored_user (
+    usr integer not null,
+    ignored_user integer not null,
+    PRIMARY KEY (usr, ignored_user)
+);
+
+CREATE TABLE IF NOT EXISTS highlight_ignored_channel (
+    usr integer not null,
+    channel_id integer not null,
+    PRIMARY KEY (usr, channel_id)
+);
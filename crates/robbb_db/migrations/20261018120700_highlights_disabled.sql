@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS highlights_disabled (
+    usr integer primary key,
+    reason text not null,
+    disabled_at datetime not null
+);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS highlight_settings (
+    usr integer primary key,
+    -- 0: immediate, 1: hourly digest, 2: daily digest
+    mode integer not null default 0,
+    last_digest_sent datetime not null
+);
+
+CREATE TABLE IF NOT EXISTS highlight_digest_entry (
+    id integer primary key autoincrement,
+    usr integer not null,
+    trigger text not null,
+    channel_id integer not null,
+    message_link text not null,
+    author integer not null,
+    create_date datetime not null
+);
+CREATE INDEX IF NOT EXISTS highlight_digest_entry_usr ON highlight_digest_entry (usr);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS highlight_limit (
+    role_id integer primary key,
+    max_highlights integer not null
+);
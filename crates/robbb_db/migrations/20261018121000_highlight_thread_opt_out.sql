@@ -0,0 +1 @@
+ALTER TABLE highlight_settings ADD COLUMN thread_highlights boolean not null default true;
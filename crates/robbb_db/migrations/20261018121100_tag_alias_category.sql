@@ -0,0 +1,6 @@
+ALTER TABLE tag ADD COLUMN category text;
+
+CREATE TABLE IF NOT EXISTS tag_alias (
+    alias text not null COLLATE NOCASE primary key,
+    tag text not null
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS tag_revision (
+    id integer primary key autoincrement,
+    tag text not null COLLATE NOCASE,
+    revision integer not null,
+    moderator integer not null,
+    content text not null,
+    create_date datetime not null,
+    UNIQUE (tag, revision)
+);
+
+-- the current state of all existing tags becomes their first revision
+INSERT INTO tag_revision (tag, revision, moderator, content, create_date)
+    SELECT name, 1, moderator, content, coalesce(create_date, datetime('now')) FROM tag;
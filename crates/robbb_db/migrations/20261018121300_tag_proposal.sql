@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS tag_proposal (
+    id integer primary key autoincrement,
+    name text not null,
+    content text not null,
+    author integer not null,
+    create_date datetime not null
+);
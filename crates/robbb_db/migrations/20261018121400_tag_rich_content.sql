@@ -0,0 +1,3 @@
+-- JSON encoded embed content in addition to the text content of a tag
+ALTER TABLE tag ADD COLUMN rich_content text;
+ALTER TABLE tag_revision ADD COLUMN rich_content text;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS tag_usage (
+    tag text not null COLLATE NOCASE,
+    day date not null,
+    channel_id integer not null,
+    count integer not null default 0,
+    PRIMARY KEY (tag, day, channel_id)
+);
@@ -0,0 +1,17 @@
+-- Full-text index over the names and content of all tags, kept in sync by the triggers below
+CREATE VIRTUAL TABLE IF NOT EXISTS tag_fts USING fts5(name, content);
+
+CREATE TRIGGER IF NOT EXISTS tag_fts_insert AFTER INSERT ON tag BEGIN
+    INSERT INTO tag_fts (name, content) VALUES (new.name, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tag_fts_delete AFTER DELETE ON tag BEGIN
+    DELETE FROM tag_fts WHERE name = old.name;
+END;
+
+CREATE TRIGGER IF NOT EXISTS tag_fts_update AFTER UPDATE OF name, content ON tag BEGIN
+    DELETE FROM tag_fts WHERE name = old.name;
+    INSERT INTO tag_fts (name, content) VALUES (new.name, new.content);
+END;
+
+INSERT INTO tag_fts (name, content) SELECT name, content FROM tag;
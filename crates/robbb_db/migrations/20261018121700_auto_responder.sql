@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS auto_responder (
+    id integer primary key autoincrement,
+    pattern text not null,
+    tag text not null COLLATE NOCASE,
+    -- comma separated channel or category ids, empty means all channels
+    channels text not null default '',
+    -- in seconds
+    cooldown integer not null,
+    added_by integer not null,
+    create_date datetime not null
+);
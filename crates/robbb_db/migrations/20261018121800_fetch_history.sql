@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS fetch_history (
+    id integer primary key autoincrement,
+    usr integer not null,
+    info text not null,
+    create_date datetime not null
+);
+
+CREATE INDEX IF NOT EXISTS fetch_history_usr ON fetch_history (usr, id);
+
+-- the current fetches become the first snapshot of everyones history
+INSERT INTO fetch_history (usr, info, create_date)
+    SELECT usr, info, coalesce(create_date, datetime('now')) FROM fetch;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS fetch_stats (
+    day date not null,
+    field text not null,
+    value text not null,
+    count integer not null,
+    total integer not null,
+    PRIMARY KEY (day, field, value)
+);
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS fetch_value_alias (
+    field text not null,
+    alias text not null COLLATE NOCASE,
+    canonical text not null,
+    added_by integer,
+    create_date datetime not null,
+    PRIMARY KEY (field, alias)
+);
+
+-- the values that used to be hardcoded as equivalent in /top
+INSERT INTO fetch_value_alias (field, alias, canonical, create_date) VALUES
+    ('Editor', 'nvim', 'neovim', datetime('now')),
+    ('Editor', 'code', 'visual studio code', datetime('now')),
+    ('Editor', 'vscode', 'visual studio code', datetime('now')),
+    ('Display Protocol', 'xorg', 'x11', datetime('now')),
+    ('Distro', 'arch', 'arch linux', datetime('now'));
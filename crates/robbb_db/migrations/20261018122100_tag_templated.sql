@@ -0,0 +1,3 @@
+-- placeholders are only substituted in tags that explicitly enable templating,
+-- so existing tags containing braces keep being sent as they are
+alter table tag add column templated boolean not null default false;
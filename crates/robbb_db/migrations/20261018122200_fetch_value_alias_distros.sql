@@ -0,0 +1,25 @@
+-- /top no longer only looks at the first word of distro names,
+-- so the common ways of spelling a distro are aliased to one name instead.
+INSERT OR IGNORE INTO fetch_value_alias (field, alias, canonical, create_date) VALUES
+    ('Distro', 'archlinux', 'arch linux', datetime('now')),
+    ('Distro', 'artix', 'artix linux', datetime('now')),
+    ('Distro', 'alpine', 'alpine linux', datetime('now')),
+    ('Distro', 'void', 'void linux', datetime('now')),
+    ('Distro', 'gentoo', 'gentoo linux', datetime('now')),
+    ('Distro', 'gentoo base system', 'gentoo linux', datetime('now')),
+    ('Distro', 'mint', 'linux mint', datetime('now')),
+    ('Distro', 'kali', 'kali linux', datetime('now')),
+    ('Distro', 'kali gnu/linux', 'kali linux', datetime('now')),
+    ('Distro', 'garuda', 'garuda linux', datetime('now')),
+    ('Distro', 'rocky', 'rocky linux', datetime('now')),
+    ('Distro', 'elementary', 'elementary os', datetime('now')),
+    ('Distro', 'zorin', 'zorin os', datetime('now')),
+    ('Distro', 'pop os', 'pop!_os', datetime('now')),
+    ('Distro', 'popos', 'pop!_os', datetime('now')),
+    ('Distro', 'endeavour os', 'endeavouros', datetime('now')),
+    ('Distro', 'nix os', 'nixos', datetime('now')),
+    ('Distro', 'tumbleweed', 'opensuse tumbleweed', datetime('now')),
+    ('Distro', 'manjaro linux', 'manjaro', datetime('now')),
+    ('Distro', 'fedora linux', 'fedora', datetime('now')),
+    ('Distro', 'debian gnu/linux', 'debian', datetime('now')),
+    ('Distro', 'slackware linux', 'slackware', datetime('now'));
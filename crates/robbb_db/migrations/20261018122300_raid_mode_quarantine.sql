@@ -0,0 +1,8 @@
+-- The members that got quarantined while a raid mode was active,
+-- so the quarantine can be lifted again once the raid mode ends.
+CREATE TABLE IF NOT EXISTS raid_mode_quarantine (
+    raid_mode integer not null,
+    usr integer not null,
+    PRIMARY KEY(raid_mode, usr),
+    FOREIGN KEY(raid_mode) REFERENCES raid_mode(id)
+);
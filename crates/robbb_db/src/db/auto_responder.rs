@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use serenity::model::id::{ChannelId, UserId};
+
+use super::highlights::{format_channel_list, parse_channel_list};
+use super::Db;
+
+/// Maximum length of the pattern of an auto-responder.
+pub const MAX_AUTO_RESPONDER_PATTERN_LEN: usize = 200;
+
+/// Limit for the compiled size of a pattern, as these get run against every message.
+const PATTERN_SIZE_LIMIT: usize = 1 << 16;
+
+/// Compile the pattern of an auto-responder, refusing patterns that are too large or too broad.
+pub fn compile_auto_responder_pattern(pattern: &str) -> Result<Regex> {
+    if pattern.len() > MAX_AUTO_RESPONDER_PATTERN_LEN {
+        bail!("Patterns can be at most {} characters long", MAX_AUTO_RESPONDER_PATTERN_LEN);
+    }
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+        .context("Invalid or too complex regex")?;
+    if regex.is_match("") {
+        bail!("Patterns must not match an empty message");
+    }
+    Ok(regex)
+}
+
+/// A rule that answers messages matching a pattern with a tag.
+#[derive(Debug, Clone)]
+pub struct AutoResponder {
+    pub id: i64,
+    pub pattern: Regex,
+    pub tag: String,
+    /// Channels or categories the rule is restricted to. Empty means all channels.
+    pub channels: Vec<ChannelId>,
+    /// Minimum time between two responses of this rule within the same channel.
+    pub cooldown: Duration,
+    pub added_by: UserId,
+}
+
+impl AutoResponder {
+    /// Check whether the rule applies to a channel.
+    /// `channel_ids` contains the channel itself followed by its parent channels and category.
+    pub fn applies_to_channel(&self, channel_ids: &[ChannelId]) -> bool {
+        self.channels.is_empty() || self.channels.iter().any(|x| channel_ids.contains(x))
+    }
+}
+
+impl Db {
+    /// Get the auto-responders whose pattern matches the given text,
+    /// without copying all the other auto-responders.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_matching_auto_responders(&self, text: &str) -> Result<Vec<AutoResponder>> {
+        let cache = self.auto_responder_cache.read().await;
+        let matching = match cache.as_ref() {
+            Some(responders) => {
+                responders.iter().filter(|x| x.pattern.is_match(text)).cloned().collect()
+            }
+            None => {
+                std::mem::drop(cache);
+                let responders = self.get_auto_responders().await?;
+                responders.into_iter().filter(|x| x.pattern.is_match(text)).collect()
+            }
+        };
+        Ok(matching)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_auto_responders(&self) -> Result<Vec<AutoResponder>> {
+        let mut cache = self.auto_responder_cache.write().await;
+        if let Some(cache) = cache.as_ref() {
+            return Ok(cache.clone());
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "select id, pattern, tag, channels, cooldown, added_by from auto_responder order by id"
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        let responders = rows
+            .into_iter()
+            .filter_map(|x| match compile_auto_responder_pattern(&x.pattern) {
+                Ok(pattern) => Some(AutoResponder {
+                    id: x.id,
+                    pattern,
+                    tag: x.tag,
+                    channels: parse_channel_list(&x.channels),
+                    cooldown: Duration::from_secs(x.cooldown as u64),
+                    added_by: UserId(x.added_by as u64),
+                }),
+                Err(err) => {
+                    tracing::warn!(
+                        "Skipping auto-responder {} with invalid pattern: {}",
+                        x.id,
+                        err
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        *cache = Some(responders.clone());
+        Ok(responders)
+    }
+
+    #[tracing::instrument(skip_all,
+        fields(auto_responder.pattern = %pattern, auto_responder.tag = %tag)
+    )]
+    pub async fn add_auto_responder(
+        &self,
+        pattern: &str,
+        tag: &str,
+        channels: Vec<ChannelId>,
+        cooldown: Duration,
+        added_by: UserId,
+    ) -> Result<AutoResponder> {
+        let regex = compile_auto_responder_pattern(pattern)?;
+        let mut conn = self.pool.acquire().await?;
+        let id = {
+            let channels = format_channel_list(&channels);
+            let cooldown = cooldown.as_secs() as i64;
+            let added_by = added_by.0 as i64;
+            let now = Utc::now();
+            sqlx::query!(
+                "insert into auto_responder (pattern, tag, channels, cooldown, added_by, create_date)
+                 values (?, ?, ?, ?, ?, ?)",
+                pattern,
+                tag,
+                channels,
+                cooldown,
+                added_by,
+                now,
+            )
+            .execute(&mut conn)
+            .await?
+            .last_insert_rowid()
+        };
+
+        let responder = AutoResponder {
+            id,
+            pattern: regex,
+            tag: tag.to_string(),
+            channels,
+            cooldown,
+            added_by,
+        };
+        let mut cache = self.auto_responder_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.push(responder.clone());
+        }
+        Ok(responder)
+    }
+
+    /// Remove an auto-responder. Returns false if there was no auto-responder with that id.
+    #[tracing::instrument(skip_all, fields(auto_responder.id = %id))]
+    pub async fn remove_auto_responder(&self, id: i64) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let removed = sqlx::query!("delete from auto_responder where id = ?", id)
+            .execute(&mut conn)
+            .await?
+            .rows_affected()
+            > 0;
+
+        let mut cache = self.auto_responder_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.retain(|x| x.id != id);
+        }
+        Ok(removed)
+    }
+}
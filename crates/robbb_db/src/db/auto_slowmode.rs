@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serenity::model::id::{ChannelId, UserId};
+
+use super::Db;
+
+/// Automatic slowmode settings of a channel.
+#[derive(Debug, Clone)]
+pub struct AutoSlowmode {
+    pub channel_id: ChannelId,
+    /// Messages per minute above which slowmode gets applied.
+    pub threshold: u32,
+    /// Upper limit for the slowmode that gets applied, in seconds.
+    pub max_slowmode: u64,
+    pub added_by: UserId,
+}
+
+impl Db {
+    #[tracing::instrument(skip_all)]
+    pub async fn get_auto_slowmodes(&self) -> Result<HashMap<ChannelId, AutoSlowmode>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let mut cache = self.auto_slowmode_cache.write().await;
+        if let Some(cache) = cache.as_ref() {
+            return Ok(cache.clone());
+        }
+
+        let settings = sqlx::query!("select * from auto_slowmode")
+            .fetch_all(&mut conn)
+            .await?
+            .into_iter()
+            .map(|x| {
+                let channel_id = ChannelId(x.channel_id as u64);
+                let settings = AutoSlowmode {
+                    channel_id,
+                    threshold: x.threshold as u32,
+                    max_slowmode: x.max_slowmode as u64,
+                    added_by: UserId(x.added_by as u64),
+                };
+                (channel_id, settings)
+            })
+            .collect::<HashMap<_, _>>();
+        *cache = Some(settings.clone());
+        Ok(settings)
+    }
+
+    /// Get the automatic slowmode settings of a single channel,
+    /// without copying the settings of all other channels.
+    #[tracing::instrument(skip_all, fields(auto_slowmode.channel_id = %channel_id.0))]
+    pub async fn get_auto_slowmode(&self, channel_id: ChannelId) -> Result<Option<AutoSlowmode>> {
+        let cache = self.auto_slowmode_cache.read().await;
+        if let Some(cache) = cache.as_ref() {
+            Ok(cache.get(&channel_id).cloned())
+        } else {
+            std::mem::drop(cache);
+            Ok(self.get_auto_slowmodes().await?.remove(&channel_id))
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(auto_slowmode.channel_id = %channel_id.0))]
+    pub async fn set_auto_slowmode(
+        &self,
+        channel_id: ChannelId,
+        threshold: u32,
+        max_slowmode: u64,
+        added_by: UserId,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        {
+            let channel_id = channel_id.0 as i64;
+            let max_slowmode = max_slowmode as i64;
+            let added_by = added_by.0 as i64;
+            sqlx::query!(
+                "insert into auto_slowmode (channel_id, threshold, max_slowmode, added_by) values (?1, ?2, ?3, ?4)
+                 on conflict(channel_id) do update set threshold=?2, max_slowmode=?3, added_by=?4",
+                channel_id,
+                threshold,
+                max_slowmode,
+                added_by,
+            )
+            .execute(&mut conn)
+            .await?;
+        }
+
+        let mut cache = self.auto_slowmode_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache
+                .insert(channel_id, AutoSlowmode { channel_id, threshold, max_slowmode, added_by });
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(auto_slowmode.channel_id = %channel_id.0))]
+    pub async fn remove_auto_slowmode(&self, channel_id: ChannelId) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let removed = {
+            let channel_id = channel_id.0 as i64;
+            sqlx::query!("delete from auto_slowmode where channel_id = ?", channel_id)
+                .execute(&mut conn)
+                .await?
+                .rows_affected()
+                > 0
+        };
+
+        let mut cache = self.auto_slowmode_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.remove(&channel_id);
+        }
+        Ok(removed)
+    }
+}
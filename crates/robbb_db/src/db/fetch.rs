@@ -8,6 +8,9 @@ use serenity::model::id::UserId;
 use super::fetch_field::{FetchField, FETCH_KEY_ORDER};
 use super::Db;
 
+/// Number of past fetches kept per user in the fetch history.
+pub const MAX_FETCH_HISTORY_PER_USER: i64 = 25;
+
 #[derive(Debug)]
 pub struct Fetch {
     pub user: UserId,
@@ -29,6 +32,7 @@ impl Fetch {
 }
 
 impl Db {
+    /// Set the fetch of a user, recording it as a new snapshot in their fetch history.
     #[tracing::instrument(skip_all)]
     pub async fn set_fetch(
         &self,
@@ -36,24 +40,76 @@ impl Db {
         info: HashMap<FetchField, String>,
         create_date: Option<DateTime<Utc>>,
     ) -> Result<Fetch> {
-        let mut conn = self.pool.acquire().await?;
+        let mut transaction = self.pool.begin().await?;
         {
             let user = user.0 as i64;
-            let info = serde_json::to_string(&info)?;
+            let info_json = serde_json::to_string(&info)?;
 
             sqlx::query!(
                 "insert into fetch (usr, info, create_date) values (?1, ?2, ?3) on conflict(usr) do update set info=?2, create_date=?3",
                 user,
-                info,
+                info_json,
                 create_date,
             )
-            .execute(&mut conn)
+            .execute(&mut transaction)
             .await?;
+
+            let latest_snapshot = sqlx::query!(
+                "select info from fetch_history where usr=? order by id desc limit 1",
+                user
+            )
+            .fetch_optional(&mut transaction)
+            .await?
+            .map(|x| serde_json::from_str::<HashMap<FetchField, String>>(&x.info))
+            .transpose()
+            .context("Failed to deserialize fetch history")?;
+
+            // don't record snapshots for updates that didn't change anything
+            if latest_snapshot.as_ref() != Some(&info) {
+                let snapshot_date = create_date.unwrap_or_else(Utc::now);
+                sqlx::query!(
+                    "insert into fetch_history (usr, info, create_date) values (?, ?, ?)",
+                    user,
+                    info_json,
+                    snapshot_date,
+                )
+                .execute(&mut transaction)
+                .await?;
+                sqlx::query!(
+                    "delete from fetch_history where usr=?1 and id not in
+                        (select id from fetch_history where usr=?1 order by id desc limit ?2)",
+                    user,
+                    MAX_FETCH_HISTORY_PER_USER,
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
         }
+        transaction.commit().await?;
 
         Ok(Fetch { user, info, create_date })
     }
 
+    /// Remove all fetch data of a user, including their fetch history.
+    #[tracing::instrument(skip_all)]
+    pub async fn clear_fetch(&self, user: UserId) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let user = user.0 as i64;
+        let now = Utc::now();
+        sqlx::query!(
+            "insert into fetch (usr, info, create_date) values (?1, '{}', ?2) on conflict(usr) do update set info='{}', create_date=?2",
+            user,
+            now,
+        )
+        .execute(&mut transaction)
+        .await?;
+        sqlx::query!("delete from fetch_history where usr=?", user)
+            .execute(&mut transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn get_fetch(&self, user: UserId) -> Result<Option<Fetch>> {
         let mut conn = self.pool.acquire().await?;
@@ -73,12 +129,18 @@ impl Db {
         }
     }
 
+    /// Set some values of a users fetch, keeping the other ones.
+    /// With `canonicalize`, values that are known aliases are stored as their canonical value.
     #[tracing::instrument(skip_all)]
     pub async fn update_fetch(
         &self,
         user: UserId,
-        new_values: HashMap<FetchField, String>,
+        mut new_values: HashMap<FetchField, String>,
+        canonicalize: bool,
     ) -> Result<Fetch> {
+        if canonicalize {
+            self.get_fetch_value_aliases().await?.canonicalize_fetch(&mut new_values);
+        }
         let mut fetch = self.get_fetch(user).await?.map(|x| x.info).unwrap_or_default();
 
         for (key, value) in new_values {
@@ -107,4 +169,24 @@ impl Db {
             })
             .collect::<Result<_>>()
     }
+
+    /// Get the past fetches of a user, oldest first.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_fetch_history(&self, user: UserId) -> Result<Vec<Fetch>> {
+        let mut conn = self.pool.acquire().await?;
+        let user_id = user.0 as i64;
+        sqlx::query!("select info, create_date from fetch_history where usr=? order by id", user_id)
+            .fetch_all(&mut conn)
+            .await?
+            .into_iter()
+            .map(|x| {
+                Ok(Fetch {
+                    user,
+                    info: serde_json::from_str(&x.info)
+                        .context("Failed to deserialize fetch data")?,
+                    create_date: Some(DateTime::from_utc(x.create_date, Utc)),
+                })
+            })
+            .collect::<Result<_>>()
+    }
 }
@@ -79,7 +79,7 @@ impl std::str::FromStr for FetchField {
             "kernel" => Ok(Self::Kernel),
             "terminal" => Ok(Self::Terminal),
             "editor" => Ok(Self::Editor),
-            "dewm" | "de" | "wm" | "de/wm" => Ok(Self::DEWM),
+            "dewm" | "de" | "wm" | "de/wm" | "de_wm" => Ok(Self::DEWM),
             "bar" => Ok(Self::Bar),
             "resolution" => Ok(Self::Resolution),
             "display protocol" => Ok(Self::DisplayProtocol),
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use super::fetch_field::FetchField;
+use super::Db;
+
+/// How many users had a value set for a fetch field on a given day.
+#[derive(Debug, Clone)]
+pub struct FetchStat {
+    pub day: NaiveDate,
+    pub value: String,
+    pub count: i64,
+    /// Amount of users that had any value set for the field on that day.
+    pub total: i64,
+}
+
+impl Db {
+    /// Get the most recent day fetch stats were recorded for.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_latest_fetch_stats_day(&self) -> Result<Option<NaiveDate>> {
+        let mut conn = self.pool.acquire().await?;
+        let day = sqlx::query!(r#"select max(day) as "day: NaiveDate" from fetch_stats"#)
+            .fetch_one(&mut conn)
+            .await?
+            .day;
+        Ok(day)
+    }
+
+    /// Store the value counts of fetch fields, replacing any stats of a field for the same day.
+    /// All stats are written in a single transaction, so a day is never recorded partially.
+    #[tracing::instrument(skip_all)]
+    pub async fn add_fetch_stats(
+        &self,
+        stats: impl IntoIterator<Item = (FetchField, FetchStat)>,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        for (field, stat) in stats {
+            let field = field.to_string();
+            sqlx::query!(
+                "insert into fetch_stats (day, field, value, count, total) values (?1, ?2, ?3, ?4, ?5)
+                 on conflict(day, field, value) do update set count=?4, total=?5",
+                stat.day,
+                field,
+                stat.value,
+                stat.count,
+                stat.total,
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Get the stats of a field from the last recorded day of every month, oldest first.
+    #[tracing::instrument(skip_all, fields(%field))]
+    pub async fn get_monthly_fetch_stats(&self, field: &FetchField) -> Result<Vec<FetchStat>> {
+        let mut conn = self.pool.acquire().await?;
+        let field = field.to_string();
+        let stats = sqlx::query!(
+            r#"select day as "day: NaiveDate", value, count, total from fetch_stats
+             where field=?1 and day in
+                (select max(day) from fetch_stats where field=?1 group by strftime('%Y-%m', day))
+             order by day, count desc"#,
+            field
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(stats
+            .into_iter()
+            .map(|x| FetchStat { day: x.day, value: x.value, count: x.count, total: x.total })
+            .collect())
+    }
+}
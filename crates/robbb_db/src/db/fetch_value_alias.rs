@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::Utc;
+use itertools::Itertools;
+use serenity::model::id::UserId;
+
+use super::fetch_field::FetchField;
+use super::Db;
+
+/// An alternative spelling of a fetch value, such as "nvim" for "neovim".
+#[derive(Debug, Clone)]
+pub struct FetchValueAlias {
+    pub field: FetchField,
+    pub alias: String,
+    pub canonical: String,
+    /// The moderator that added the alias. `None` for the aliases that came with the bot.
+    pub added_by: Option<UserId>,
+}
+
+/// All fetch value aliases, by field and lowercase alias.
+#[derive(Debug, Clone, Default)]
+pub struct FetchValueAliases {
+    aliases: HashMap<FetchField, HashMap<String, FetchValueAlias>>,
+}
+
+impl FetchValueAliases {
+    /// Get the canonical value for a value of a field, if the value is a known alias.
+    /// Aliases are matched case-insensitively against the whole value.
+    pub fn canonicalize(&self, field: &FetchField, value: &str) -> Option<&str> {
+        let alias = self.aliases.get(field)?.get(&value.trim().to_lowercase())?;
+        Some(alias.canonical.as_str())
+    }
+
+    /// Replace all values of a fetch that are known aliases with their canonical value.
+    pub fn canonicalize_fetch(&self, info: &mut HashMap<FetchField, String>) {
+        for (field, value) in info.iter_mut() {
+            if let Some(canonical) = self.canonicalize(field, value) {
+                *value = canonical.to_string();
+            }
+        }
+    }
+
+    /// Check whether a value is used as the canonical value of some alias of the field.
+    pub fn is_canonical(&self, field: &FetchField, value: &str) -> bool {
+        self.aliases
+            .get(field)
+            .map_or(false, |x| x.values().any(|x| x.canonical.eq_ignore_ascii_case(value)))
+    }
+
+    /// Get all aliases, ordered by field and canonical value.
+    pub fn all(&self) -> Vec<&FetchValueAlias> {
+        self.aliases
+            .values()
+            .flat_map(|x| x.values())
+            .sorted_by_key(|x| (x.field.to_string(), x.canonical.to_lowercase(), &x.alias))
+            .collect()
+    }
+
+    /// Add an alias, replacing any existing alias of the same value.
+    pub fn insert(&mut self, alias: FetchValueAlias) {
+        let field_aliases = self.aliases.entry(alias.field.clone()).or_default();
+        field_aliases.insert(alias.alias.to_lowercase(), alias);
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all)]
+    pub async fn get_fetch_value_aliases(&self) -> Result<FetchValueAliases> {
+        let mut cache = self.fetch_value_alias_cache.write().await;
+        if let Some(cache) = cache.as_ref() {
+            return Ok(cache.clone());
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!("select field, alias, canonical, added_by from fetch_value_alias")
+            .fetch_all(&mut conn)
+            .await?;
+        let mut aliases = FetchValueAliases::default();
+        for row in rows {
+            match FetchField::from_str(&row.field) {
+                Ok(field) => aliases.insert(FetchValueAlias {
+                    field,
+                    alias: row.alias,
+                    canonical: row.canonical,
+                    added_by: row.added_by.map(|x| UserId(x as u64)),
+                }),
+                Err(_) => tracing::warn!("Skipping alias of unknown fetch field {}", row.field),
+            }
+        }
+
+        *cache = Some(aliases.clone());
+        Ok(aliases)
+    }
+
+    /// Add an alias for a fetch value, replacing the canonical value if the alias already exists.
+    #[tracing::instrument(skip_all, fields(%field, fetch_value_alias.alias = %alias))]
+    pub async fn add_fetch_value_alias(
+        &self,
+        field: FetchField,
+        alias: &str,
+        canonical: &str,
+        added_by: UserId,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        {
+            let field = field.to_string();
+            let added_by = added_by.0 as i64;
+            let now = Utc::now();
+            sqlx::query!(
+                "insert into fetch_value_alias (field, alias, canonical, added_by, create_date)
+                 values (?1, ?2, ?3, ?4, ?5)
+                 on conflict(field, alias) do update set canonical=?3, added_by=?4, create_date=?5",
+                field,
+                alias,
+                canonical,
+                added_by,
+                now,
+            )
+            .execute(&mut conn)
+            .await?;
+        }
+
+        let mut cache = self.fetch_value_alias_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.insert(FetchValueAlias {
+                field,
+                alias: alias.to_string(),
+                canonical: canonical.to_string(),
+                added_by: Some(added_by),
+            });
+        }
+        Ok(())
+    }
+
+    /// Remove an alias for a fetch value. Returns false if there was no such alias.
+    #[tracing::instrument(skip_all, fields(%field, fetch_value_alias.alias = %alias))]
+    pub async fn remove_fetch_value_alias(&self, field: &FetchField, alias: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let removed = {
+            let field = field.to_string();
+            sqlx::query!("delete from fetch_value_alias where field=? and alias=?", field, alias)
+                .execute(&mut conn)
+                .await?
+                .rows_affected()
+                > 0
+        };
+
+        let mut cache = self.fetch_value_alias_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            if let Some(field_aliases) = cache.aliases.get_mut(field) {
+                field_aliases.remove(&alias.to_lowercase());
+            }
+        }
+        Ok(removed)
+    }
+}
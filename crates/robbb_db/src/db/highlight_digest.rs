@@ -0,0 +1,227 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serenity::model::id::{ChannelId, UserId};
+
+use super::Db;
+
+/// How a user gets notified about their highlights.
+#[derive(Debug, Eq, Copy, Clone, PartialEq, Hash, poise::ChoiceParameter)]
+pub enum HighlightMode {
+    Immediate,
+    Hourly,
+    Daily,
+}
+
+impl HighlightMode {
+    pub fn from_i32(n: i32) -> Result<Self> {
+        match n {
+            0 => Ok(HighlightMode::Immediate),
+            1 => Ok(HighlightMode::Hourly),
+            2 => Ok(HighlightMode::Daily),
+            _ => anyhow::bail!("Invalid highlight mode: {}", n),
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            HighlightMode::Immediate => 0,
+            HighlightMode::Hourly => 1,
+            HighlightMode::Daily => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for HighlightMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HighlightMode::Immediate => write!(f, "Immediate"),
+            HighlightMode::Hourly => write!(f, "Hourly digest"),
+            HighlightMode::Daily => write!(f, "Daily digest"),
+        }
+    }
+}
+
+/// Per-user settings for how highlights get delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSettings {
+    pub mode: HighlightMode,
+    /// Whether the user gets highlighted for messages inside of threads.
+    pub thread_highlights: bool,
+}
+
+impl Default for HighlightSettings {
+    fn default() -> Self {
+        HighlightSettings { mode: HighlightMode::Immediate, thread_highlights: true }
+    }
+}
+
+/// A highlight match waiting to be sent as part of a digest.
+#[derive(Debug, Clone)]
+pub struct HighlightDigestEntry {
+    pub user: UserId,
+    pub trigger: String,
+    pub channel_id: ChannelId,
+    pub message_link: String,
+    pub author: UserId,
+    pub create_date: DateTime<Utc>,
+}
+
+struct DbHighlightDigestEntryFields {
+    usr: i64,
+    trigger: String,
+    channel_id: i64,
+    message_link: String,
+    author: i64,
+    create_date: NaiveDateTime,
+}
+
+impl DbHighlightDigestEntryFields {
+    fn into_highlight_digest_entry(self) -> HighlightDigestEntry {
+        HighlightDigestEntry {
+            user: UserId(self.usr as u64),
+            trigger: self.trigger,
+            channel_id: ChannelId(self.channel_id as u64),
+            message_link: self.message_link,
+            author: UserId(self.author as u64),
+            create_date: DateTime::from_utc(self.create_date, Utc),
+        }
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all)]
+    pub async fn get_highlight_settings(&self, user: UserId) -> Result<HighlightSettings> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let settings = sqlx::query!(
+            "select mode, thread_highlights from highlight_settings where usr = ?",
+            user
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        match settings {
+            Some(settings) => Ok(HighlightSettings {
+                mode: HighlightMode::from_i32(settings.mode as i32)?,
+                thread_highlights: settings.thread_highlights,
+            }),
+            None => Ok(HighlightSettings::default()),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(highlights.mode = %mode))]
+    pub async fn set_highlight_mode(&self, user: UserId, mode: HighlightMode) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let mode = mode.as_i32();
+        let now = Utc::now();
+        sqlx::query!(
+            "insert into highlight_settings (usr, mode, last_digest_sent) values (?1, ?2, ?3)
+             on conflict(usr) do update set mode=?2, last_digest_sent=?3",
+            user,
+            mode,
+            now,
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(highlights.thread_highlights = %enabled))]
+    pub async fn set_thread_highlights(&self, user: UserId, enabled: bool) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let now = Utc::now();
+        sqlx::query!(
+            "insert into highlight_settings (usr, thread_highlights, last_digest_sent)
+             values (?1, ?2, ?3)
+             on conflict(usr) do update set thread_highlights=?2",
+            user,
+            enabled,
+            now,
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn add_highlight_digest_entry(&self, entry: HighlightDigestEntry) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let user = entry.user.0 as i64;
+        let channel_id = entry.channel_id.0 as i64;
+        let author = entry.author.0 as i64;
+        sqlx::query!(
+            "insert into highlight_digest_entry
+             (usr, trigger, channel_id, message_link, author, create_date)
+             values (?, ?, ?, ?, ?, ?)",
+            user,
+            entry.trigger,
+            channel_id,
+            entry.message_link,
+            author,
+            entry.create_date,
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the users that have buffered highlight matches and are due to get their digest.
+    /// Users that switched back to immediate notifications get their remaining matches right away.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_users_with_due_highlight_digest(&self) -> Result<Vec<UserId>> {
+        let mut conn = self.pool.acquire().await?;
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        let day_ago = Utc::now() - chrono::Duration::days(1);
+        let users = sqlx::query!(
+            "select distinct highlight_digest_entry.usr from highlight_digest_entry
+             left join highlight_settings on highlight_settings.usr = highlight_digest_entry.usr
+             where highlight_settings.mode is null
+                or highlight_settings.mode = 0
+                or (highlight_settings.mode = 1 and highlight_settings.last_digest_sent <= ?1)
+                or (highlight_settings.mode = 2 and highlight_settings.last_digest_sent <= ?2)",
+            hour_ago,
+            day_ago,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(users.into_iter().map(|x| UserId(x.usr as u64)).collect())
+    }
+
+    /// Get all buffered highlight matches of a user, oldest first.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_highlight_digest(&self, user: UserId) -> Result<Vec<HighlightDigestEntry>> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let entries = sqlx::query_as!(
+            DbHighlightDigestEntryFields,
+            "select usr, trigger, channel_id, message_link, author, create_date
+             from highlight_digest_entry where usr = ? order by create_date",
+            user
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(entries.into_iter().map(|x| x.into_highlight_digest_entry()).collect())
+    }
+
+    /// Remove the buffered highlight matches of a user up to and including `up_to`,
+    /// marking their digest as sent. Matches that came in later are kept for the next digest.
+    #[tracing::instrument(skip_all)]
+    pub async fn clear_highlight_digest(&self, user: UserId, up_to: DateTime<Utc>) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let user = user.0 as i64;
+        sqlx::query!(
+            "delete from highlight_digest_entry where usr = ? and create_date <= ?",
+            user,
+            up_to
+        )
+        .execute(&mut transaction)
+        .await?;
+        let now = Utc::now();
+        sqlx::query!("update highlight_settings set last_digest_sent = ? where usr = ?", now, user)
+            .execute(&mut transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
@@ -1,99 +1,281 @@
 use super::*;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+use chrono::Utc;
 use itertools::Itertools;
+use regex::{Regex, RegexBuilder};
+use serenity::model::id::RoleId;
 use std::collections::HashSet;
 
-fn combine_multitrigger_regex<'a, I: IntoIterator<Item = &'a str>>(
-    words: I,
-) -> Result<regex::Regex> {
-    let joined_words = words.into_iter().map(regex::escape).join("|");
-    let mut regex_builder = regex::RegexBuilder::new(&format!(r"\b(?:{})\b", joined_words));
-    regex_builder
+/// Maximum length of the pattern of a regex highlight.
+pub const MAX_REGEX_TRIGGER_LEN: usize = 100;
+
+/// Limit for the compiled size of a regex highlight, as these get run against every message.
+const REGEX_TRIGGER_SIZE_LIMIT: usize = 1 << 16;
+
+/// Turn a literal trigger or a message into the form triggers are matched in,
+/// so that case and differing whitespace within phrases don't matter.
+fn normalize_trigger(trigger: &str) -> String {
+    trigger.split_whitespace().join(" ").to_lowercase()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Check that a match isn't just a part of a longer word.
+/// Like regex's `\b`, this only applies to the ends of the match that are word characters.
+fn is_word_boundary_match(haystack: &str, start: usize, end: usize) -> bool {
+    let matched = &haystack[start..end];
+    let joins_word = |a: Option<char>, b: Option<char>| {
+        a.map_or(false, is_word_char) && b.map_or(false, is_word_char)
+    };
+    !joins_word(haystack[..start].chars().next_back(), matched.chars().next())
+        && !joins_word(matched.chars().next_back(), haystack[end..].chars().next())
+}
+
+/// Matches all literal triggers in a single pass over the message.
+#[derive(Debug, Clone)]
+pub struct LiteralMatcher {
+    automaton: AhoCorasick,
+    /// Normalized triggers, indexed by their pattern id in the automaton.
+    triggers: Vec<String>,
+}
+
+impl LiteralMatcher {
+    /// Build a matcher for triggers that have already been normalized.
+    pub fn new<'a, I: IntoIterator<Item = &'a str>>(triggers: I) -> Self {
+        let triggers = triggers.into_iter().map(|x| x.to_string()).collect_vec();
+        let automaton = AhoCorasickBuilder::new().build(&triggers);
+        LiteralMatcher { automaton, triggers }
+    }
+
+    /// Find the triggers contained in a message that has already been normalized.
+    pub fn find_triggers<'a>(&'a self, normalized: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        // overlapping matches are needed, as a shorter trigger may match where a longer one doesn't
+        self.automaton
+            .find_overlapping_iter(normalized)
+            .filter(move |m| is_word_boundary_match(normalized, m.start(), m.end()))
+            .map(move |m| self.triggers[m.pattern()].as_str())
+    }
+}
+
+/// Ordinary messages used to detect regex highlights that would match nearly everything.
+const REGEX_TRIGGER_SAMPLE_MESSAGES: &[&str] = &[
+    "hi",
+    "ok",
+    "lol",
+    "Hello everyone, how are you doing today?",
+    "I think that should work, thanks for the help!",
+    "Did you try restarting it?",
+    "nice setup :)",
+    "What are you all up to this weekend?",
+    "yeah",
+    "That looks really good, what colors did you use?",
+];
+
+/// Maximum amount of [REGEX_TRIGGER_SAMPLE_MESSAGES] a regex highlight may match.
+const MAX_REGEX_TRIGGER_SAMPLE_MATCHES: usize = 2;
+
+/// Compile the pattern of a regex highlight, refusing patterns that are too large or too broad.
+/// A pattern is too broad if it matches the empty string or more than
+/// [MAX_REGEX_TRIGGER_SAMPLE_MATCHES] of a set of ordinary sample messages.
+pub fn compile_regex_trigger(pattern: &str) -> Result<Regex> {
+    if pattern.len() > MAX_REGEX_TRIGGER_LEN {
+        bail!("Regex highlights can be at most {} characters long", MAX_REGEX_TRIGGER_LEN);
+    }
+    let regex = RegexBuilder::new(pattern)
         .case_insensitive(true)
+        .size_limit(REGEX_TRIGGER_SIZE_LIMIT)
+        .dfa_size_limit(REGEX_TRIGGER_SIZE_LIMIT)
         .build()
-        .context("Failed to compile highlight trigger regex")
+        .context("Invalid or too complex regex")?;
+    if regex.is_match("") {
+        bail!("Regex highlights must not match an empty message");
+    }
+    let sample_matches = REGEX_TRIGGER_SAMPLE_MESSAGES.iter().filter(|x| regex.is_match(x)).count();
+    if sample_matches > MAX_REGEX_TRIGGER_SAMPLE_MATCHES {
+        bail!("Regex highlights must not match most ordinary messages");
+    }
+    Ok(regex)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub trigger: String,
+    pub user: UserId,
+    /// Whether the trigger is a regex rather than a literal word or phrase.
+    pub is_regex: bool,
+    /// Channels or categories the highlight is restricted to. Empty means all channels.
+    pub include_channels: Vec<ChannelId>,
+    /// Channels or categories in which the highlight never triggers.
+    pub exclude_channels: Vec<ChannelId>,
+}
+
+impl Highlight {
+    /// Check whether the highlight applies to a channel.
+    /// `channel_ids` contains the channel followed by its parents, i.e. the parent channel
+    /// of a thread and the category.
+    pub fn applies_to_channel(&self, channel_ids: &[ChannelId]) -> bool {
+        let matches = |x: &ChannelId| channel_ids.contains(x);
+        !self.exclude_channels.iter().any(matches)
+            && (self.include_channels.is_empty() || self.include_channels.iter().any(matches))
+    }
+}
+
+pub(crate) fn parse_channel_list(value: &str) -> Vec<ChannelId> {
+    value.split(',').filter_map(|x| x.trim().parse::<u64>().ok()).map(ChannelId).collect()
+}
+
+pub(crate) fn format_channel_list(channels: &[ChannelId]) -> String {
+    channels.iter().map(|x| x.0.to_string()).join(",")
+}
+
+/// Users and channels a user doesn't want to get highlight notifications for.
+#[derive(Debug, Clone, Default)]
+pub struct HighlightIgnores {
+    pub users: HashSet<UserId>,
+    /// Ignored channels or categories.
+    pub channels: HashSet<ChannelId>,
+}
+
+impl HighlightIgnores {
+    /// `channel_ids` contains the channel followed by its parents, like in
+    /// [Highlight::applies_to_channel].
+    pub fn ignores_message(&self, author: UserId, channel_ids: &[ChannelId]) -> bool {
+        self.users.contains(&author) || channel_ids.iter().any(|x| self.channels.contains(x))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct HighlightsData {
-    pub entries: HashMap<String, Vec<UserId>>,
-    combined_regex: regex::Regex,
+    /// Literal highlights, keyed by their normalized trigger.
+    pub entries: HashMap<String, Vec<Highlight>>,
+    literal_matcher: LiteralMatcher,
+    regex_entries: Vec<(Regex, Highlight)>,
+    /// Users whose highlights are disabled because they can't be notified.
+    disabled_users: HashSet<UserId>,
 }
 
 impl HighlightsData {
-    pub fn from_entries(entries: Vec<(String, Vec<UserId>)>) -> Result<Self> {
-        let triggers_regex = combine_multitrigger_regex(entries.iter().map(|(x, _)| x.as_str()))?;
-        Ok(HighlightsData {
-            entries: entries
-                .into_iter()
-                .map(|(trigger, users)| (trigger.to_lowercase(), users))
-                .collect(),
-            combined_regex: triggers_regex,
-        })
+    pub fn from_highlights(
+        highlights: Vec<Highlight>,
+        disabled_users: HashSet<UserId>,
+    ) -> Result<Self> {
+        let (regex_highlights, literal_highlights): (Vec<_>, Vec<_>) =
+            highlights.into_iter().partition(|x| x.is_regex);
+
+        let regex_entries = regex_highlights
+            .into_iter()
+            .filter_map(|highlight| match compile_regex_trigger(&highlight.trigger) {
+                Ok(regex) => Some((regex, highlight)),
+                Err(err) => {
+                    tracing::warn!(
+                        highlights.word = %highlight.trigger,
+                        "Skipping invalid regex highlight: {:#}", err
+                    );
+                    None
+                }
+            })
+            .collect();
+        let entries: HashMap<_, _> = literal_highlights
+            .into_iter()
+            .map(|x| (normalize_trigger(&x.trigger), x))
+            .into_group_map();
+        let literal_matcher = LiteralMatcher::new(entries.keys().map(|x| x.as_str()));
+        Ok(HighlightsData { entries, literal_matcher, regex_entries, disabled_users })
     }
 
+    /// Find the highlights triggered by a message, grouped by the trigger that matched.
+    /// Highlights of users that have their highlights disabled are left out.
     #[tracing::instrument(skip_all, fields(msg.content = %s))]
-    pub fn get_triggers_for_message(&self, s: &str) -> Vec<(String, Vec<UserId>)> {
-        self.combined_regex
-            .find_iter(s)
-            .filter_map(|m| {
-                let trigger = m.as_str();
-                Some((trigger.to_string(), self.entries.get(&trigger.to_lowercase())?.clone()))
-            })
-            .collect()
+    pub fn get_triggers_for_message(&self, s: &str) -> Vec<(String, Vec<Highlight>)> {
+        let is_enabled = |x: &Highlight| !self.disabled_users.contains(&x.user);
+        let normalized = normalize_trigger(s);
+        let literal_matches =
+            self.literal_matcher.find_triggers(&normalized).unique().filter_map(|trigger| {
+                let highlights = self
+                    .entries
+                    .get(trigger)?
+                    .iter()
+                    .filter(|x| is_enabled(x))
+                    .cloned()
+                    .collect_vec();
+                (!highlights.is_empty()).then(|| (trigger.to_string(), highlights))
+            });
+        let regex_matches = self
+            .regex_entries
+            .iter()
+            .filter(|(regex, highlight)| is_enabled(highlight) && regex.is_match(s))
+            .map(|(_, highlight)| (highlight.trigger.clone(), vec![highlight.clone()]));
+        literal_matches.chain(regex_matches).collect()
     }
 
     #[tracing::instrument(skip_all)]
-    pub fn triggers_for_user(&self, user_id: UserId) -> impl Iterator<Item = &str> {
+    pub fn highlights_for_user(&self, user_id: UserId) -> impl Iterator<Item = &Highlight> {
         self.entries
-            .iter()
-            .filter(move |(_, users)| users.contains(&user_id))
-            .map(|(trigger, _)| trigger.as_str())
+            .values()
+            .flatten()
+            .chain(self.regex_entries.iter().map(|(_, highlight)| highlight))
+            .filter(move |x| x.user == user_id)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn triggers_for_user(&self, user_id: UserId) -> impl Iterator<Item = &str> {
+        self.highlights_for_user(user_id).map(|x| x.trigger.as_str())
+    }
+
+    fn rebuild_literal_matcher(&mut self) {
+        self.literal_matcher = LiteralMatcher::new(self.entries.keys().map(|x| x.as_str()));
     }
 
     fn remove_entry(&mut self, trigger: &str, user: UserId) -> Result<()> {
-        let user_list =
-            self.entries.get_mut(&trigger.to_lowercase()).context("No entry with that trigger")?;
-        user_list.retain(|x| x != &user);
-        if user_list.is_empty() {
-            self.entries.remove(&trigger.to_lowercase());
-            self.combined_regex =
-                combine_multitrigger_regex(self.entries.iter().map(|(x, _)| x.as_str()))?;
+        let regex_count = self.regex_entries.len();
+        self.regex_entries.retain(|(_, x)| !(x.user == user && x.trigger == trigger));
+        if self.regex_entries.len() != regex_count {
+            return Ok(());
+        }
+
+        let normalized = normalize_trigger(trigger);
+        let highlights = self.entries.get_mut(&normalized).context("No entry with that trigger")?;
+        highlights.retain(|x| x.user != user);
+        if highlights.is_empty() {
+            self.entries.remove(&normalized);
+            self.rebuild_literal_matcher();
         }
         Ok(())
     }
 
-    fn add_entry(&mut self, trigger: String, user: UserId) -> Result<()> {
-        let already_in_regex = self.entries.contains_key(&trigger.to_lowercase());
-        self.entries
-            .entry(trigger)
-            .and_modify(|f| {
-                f.push(user);
-            })
-            .or_insert_with(|| vec![user]);
-        if !already_in_regex {
-            self.combined_regex =
-                combine_multitrigger_regex(self.entries.iter().map(|(x, _)| x.as_str()))?;
+    fn add_entry(&mut self, highlight: Highlight) -> Result<()> {
+        if highlight.is_regex {
+            let regex = compile_regex_trigger(&highlight.trigger)?;
+            self.regex_entries.push((regex, highlight));
+            return Ok(());
+        }
+        let normalized = normalize_trigger(&highlight.trigger);
+        let already_in_matcher = self.entries.contains_key(&normalized);
+        self.entries.entry(normalized).or_default().push(highlight);
+        if !already_in_matcher {
+            self.rebuild_literal_matcher();
         }
         Ok(())
     }
 
     fn remove_entries_of(&mut self, user: UserId) -> Result<()> {
-        for users in self.entries.values_mut() {
-            users.retain(|u| u != &user);
+        self.regex_entries.retain(|(_, x)| x.user != user);
+        for highlights in self.entries.values_mut() {
+            highlights.retain(|x| x.user != user);
         }
         let old_length = self.entries.len();
 
-        self.entries.retain(|_, users| !users.is_empty());
+        self.entries.retain(|_, highlights| !highlights.is_empty());
 
-        // update the regex if some words have been removed
+        // update the matcher if some words have been removed
         if self.entries.len() != old_length {
-            self.combined_regex =
-                combine_multitrigger_regex(self.entries.iter().map(|(x, _)| x.as_str()))?;
+            self.rebuild_literal_matcher();
         }
         Ok(())
     }
 }
-
 impl Db {
     #[tracing::instrument(skip_all)]
     pub async fn get_highlights(&self) -> Result<HighlightsData> {
@@ -102,14 +284,27 @@ impl Db {
             Ok(cache.clone())
         } else {
             let mut conn = self.pool.acquire().await?;
-            let entries = sqlx::query!("select * from highlights")
+            let highlights = sqlx::query!("select * from highlights")
+                .fetch_all(&mut conn)
+                .await?
+                .into_iter()
+                .map(|x| Highlight {
+                    trigger: x.word,
+                    user: UserId::from(x.usr as u64),
+                    is_regex: x.is_regex,
+                    include_channels: parse_channel_list(&x.include_channels),
+                    exclude_channels: parse_channel_list(&x.exclude_channels),
+                })
+                .collect();
+
+            let disabled_users = sqlx::query!("select usr from highlights_disabled")
                 .fetch_all(&mut conn)
                 .await?
                 .into_iter()
-                .map(|x| (x.word, UserId::from(x.usr as u64)))
-                .into_group_map();
+                .map(|x| UserId(x.usr as u64))
+                .collect();
 
-            let highlight_data = HighlightsData::from_entries(entries.into_iter().collect())?;
+            let highlight_data = HighlightsData::from_highlights(highlights, disabled_users)?;
             cache.replace(highlight_data.clone());
             Ok(highlight_data)
         }
@@ -132,24 +327,36 @@ impl Db {
     }
 
     #[tracing::instrument(skip_all)]
-    pub async fn set_highlight(&self, user: UserId, word: String) -> Result<()> {
-        if BLOCKED_WORDS.contains(&unicase::Ascii::new(word.as_str())) {
+    pub async fn set_highlight(&self, highlight: Highlight) -> Result<()> {
+        if highlight.is_regex {
+            compile_regex_trigger(&highlight.trigger)?;
+        } else if BLOCKED_WORDS.contains(&unicase::Ascii::new(highlight.trigger.as_str())) {
             bail!(
                 "Refused to set a highlight for common word {} (requested by user {})",
-                word,
-                user
+                highlight.trigger,
+                highlight.user
             );
         }
         {
             let mut conn = self.pool.acquire().await?;
-            let user = user.0 as i64;
-            sqlx::query!("insert into highlights (word, usr) values (?, ?)", word, user)
-                .execute(&mut conn)
-                .await?;
+            let user = highlight.user.0 as i64;
+            let include_channels = format_channel_list(&highlight.include_channels);
+            let exclude_channels = format_channel_list(&highlight.exclude_channels);
+            sqlx::query!(
+                "insert into highlights (word, usr, is_regex, include_channels, exclude_channels)
+                 values (?, ?, ?, ?, ?)",
+                highlight.trigger,
+                user,
+                highlight.is_regex,
+                include_channels,
+                exclude_channels,
+            )
+            .execute(&mut conn)
+            .await?;
         }
         let mut cache = self.highlight_cache.write().await;
         if let Some(ref mut cache) = cache.as_mut() {
-            cache.add_entry(word, user)?;
+            cache.add_entry(highlight)?;
         }
 
         Ok(())
@@ -170,6 +377,203 @@ impl Db {
         Ok(())
     }
 
+    /// Get the reason a user's highlights have been disabled for, if they are.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_highlights_disabled_reason(&self, user: UserId) -> Result<Option<String>> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let reason = sqlx::query!("select reason from highlights_disabled where usr = ?", user)
+            .fetch_optional(&mut conn)
+            .await?
+            .map(|x| x.reason);
+        Ok(reason)
+    }
+
+    #[tracing::instrument(skip_all, fields(highlights.disabled_reason = %reason))]
+    pub async fn disable_highlights(&self, user: UserId, reason: &str) -> Result<()> {
+        {
+            let mut conn = self.pool.acquire().await?;
+            let user = user.0 as i64;
+            let now = Utc::now();
+            sqlx::query!(
+                "insert into highlights_disabled (usr, reason, disabled_at) values (?1, ?2, ?3)
+                 on conflict(usr) do update set reason=?2, disabled_at=?3",
+                user,
+                reason,
+                now,
+            )
+            .execute(&mut conn)
+            .await?;
+        }
+        let mut cache = self.highlight_cache.write().await;
+        if let Some(ref mut cache) = cache.as_mut() {
+            cache.disabled_users.insert(user);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn enable_highlights(&self, user: UserId) -> Result<()> {
+        {
+            let mut conn = self.pool.acquire().await?;
+            let user = user.0 as i64;
+            sqlx::query!("delete from highlights_disabled where usr = ?", user)
+                .execute(&mut conn)
+                .await?;
+        }
+        let mut cache = self.highlight_cache.write().await;
+        if let Some(ref mut cache) = cache.as_mut() {
+            cache.disabled_users.remove(&user);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_highlight_ignores(&self, user: UserId) -> Result<HighlightIgnores> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let users =
+            sqlx::query!("select ignored_user from highlight_ignored_user where usr = ?", user)
+                .fetch_all(&mut conn)
+                .await?
+                .into_iter()
+                .map(|x| UserId(x.ignored_user as u64))
+                .collect();
+        let channels =
+            sqlx::query!("select channel_id from highlight_ignored_channel where usr = ?", user)
+                .fetch_all(&mut conn)
+                .await?
+                .into_iter()
+                .map(|x| ChannelId(x.channel_id as u64))
+                .collect();
+        Ok(HighlightIgnores { users, channels })
+    }
+
+    /// Ignore highlights triggered by another user. Returns false if they were already ignored.
+    #[tracing::instrument(skip_all)]
+    pub async fn add_highlight_ignored_user(&self, user: UserId, ignored: UserId) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let ignored = ignored.0 as i64;
+        let added = sqlx::query!(
+            "insert or ignore into highlight_ignored_user (usr, ignored_user) values (?, ?)",
+            user,
+            ignored
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected()
+            > 0;
+        Ok(added)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn remove_highlight_ignored_user(
+        &self,
+        user: UserId,
+        ignored: UserId,
+    ) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let ignored = ignored.0 as i64;
+        let removed = sqlx::query!(
+            "delete from highlight_ignored_user where usr = ? and ignored_user = ?",
+            user,
+            ignored
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected()
+            > 0;
+        Ok(removed)
+    }
+
+    /// Ignore highlights in a channel or category. Returns false if it was already ignored.
+    #[tracing::instrument(skip_all)]
+    pub async fn add_highlight_ignored_channel(
+        &self,
+        user: UserId,
+        channel_id: ChannelId,
+    ) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let channel_id = channel_id.0 as i64;
+        let added = sqlx::query!(
+            "insert or ignore into highlight_ignored_channel (usr, channel_id) values (?, ?)",
+            user,
+            channel_id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected()
+            > 0;
+        Ok(added)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn remove_highlight_ignored_channel(
+        &self,
+        user: UserId,
+        channel_id: ChannelId,
+    ) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        let channel_id = channel_id.0 as i64;
+        let removed = sqlx::query!(
+            "delete from highlight_ignored_channel where usr = ? and channel_id = ?",
+            user,
+            channel_id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected()
+            > 0;
+        Ok(removed)
+    }
+
+    /// Get the configured maximum amount of highlights for members of each role.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_highlight_limits(&self) -> Result<HashMap<RoleId, usize>> {
+        let mut conn = self.pool.acquire().await?;
+        let limits = sqlx::query!("select role_id, max_highlights from highlight_limit")
+            .fetch_all(&mut conn)
+            .await?
+            .into_iter()
+            .map(|x| (RoleId(x.role_id as u64), x.max_highlights as usize))
+            .collect();
+        Ok(limits)
+    }
+
+    #[tracing::instrument(skip_all,
+        fields(highlights.role_id = %role_id, highlights.limit = %limit)
+    )]
+    pub async fn set_highlight_limit(&self, role_id: RoleId, limit: usize) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let role_id = role_id.0 as i64;
+        let limit = limit as i64;
+        sqlx::query!(
+            "insert into highlight_limit (role_id, max_highlights) values (?1, ?2)
+             on conflict(role_id) do update set max_highlights=?2",
+            role_id,
+            limit,
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(highlights.role_id = %role_id))]
+    pub async fn remove_highlight_limit(&self, role_id: RoleId) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let role_id = role_id.0 as i64;
+        let removed = sqlx::query!("delete from highlight_limit where role_id = ?", role_id)
+            .execute(&mut conn)
+            .await?
+            .rows_affected()
+            > 0;
+        Ok(removed)
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn remove_forbidden_highlights(&self) -> Result<()> {
         let mut conn = self.pool.acquire().await?;
@@ -178,9 +582,12 @@ impl Db {
 
         // sqlx does not provide any way to properly embed a list here, so we godda use format!().
         // MAKE SURE that word_list never turns into user-provided input!
-        sqlx::query(&format!("delete from highlights where lower(word) in ({})", word_list,))
-            .execute(&mut conn)
-            .await?;
+        sqlx::query(&format!(
+            "delete from highlights where not is_regex and lower(word) in ({})",
+            word_list,
+        ))
+        .execute(&mut conn)
+        .await?;
         Ok(())
     }
 }
@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serenity::model::id::{GuildId, UserId};
+
+use super::Db;
+
+/// Domains and invite target guilds the link filter knows about.
+#[derive(Debug, Clone, Default)]
+pub struct LinkFilterData {
+    /// Domains mapped to whether they are allowed (`true`) or blocked (`false`).
+    pub domains: HashMap<String, bool>,
+    pub allowed_invite_guilds: HashSet<GuildId>,
+}
+
+impl LinkFilterData {
+    /// Check whether a domain is blocked. Entries also apply to all subdomains of their domain,
+    /// with the most specific entry taking precedence.
+    pub fn is_domain_blocked(&self, domain: &str) -> bool {
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        let mut rest = domain.as_str();
+        loop {
+            if let Some(allowed) = self.domains.get(rest) {
+                return !allowed;
+            }
+            match rest.split_once('.') {
+                Some((_, parent)) => rest = parent,
+                None => return false,
+            }
+        }
+    }
+
+    pub fn blocked_domains(&self) -> impl Iterator<Item = &str> {
+        self.domains.iter().filter(|(_, allowed)| !**allowed).map(|(domain, _)| domain.as_str())
+    }
+
+    pub fn allowed_domains(&self) -> impl Iterator<Item = &str> {
+        self.domains.iter().filter(|(_, allowed)| **allowed).map(|(domain, _)| domain.as_str())
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all)]
+    pub async fn get_link_filter(&self) -> Result<LinkFilterData> {
+        let mut conn = self.pool.acquire().await?;
+
+        let mut cache = self.link_filter_cache.write().await;
+        if let Some(cache) = cache.as_ref() {
+            return Ok(cache.clone());
+        }
+
+        let domains =
+            sqlx::query!(r#"select domain as "domain!", allowed from link_filter_domain"#)
+                .fetch_all(&mut conn)
+                .await?
+                .into_iter()
+                .map(|x| (x.domain, x.allowed))
+                .collect();
+        let allowed_invite_guilds = sqlx::query!("select guild_id from link_filter_invite_guild")
+            .fetch_all(&mut conn)
+            .await?
+            .into_iter()
+            .map(|x| GuildId(x.guild_id as u64))
+            .collect();
+
+        let data = LinkFilterData { domains, allowed_invite_guilds };
+        *cache = Some(data.clone());
+        Ok(data)
+    }
+
+    #[tracing::instrument(skip_all,
+        fields(link_filter.domain = %domain, link_filter.allowed = %allowed)
+    )]
+    pub async fn set_link_filter_domain(
+        &self,
+        domain: &str,
+        allowed: bool,
+        added_by: UserId,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        {
+            let added_by = added_by.0 as i64;
+            sqlx::query!(
+                "insert into link_filter_domain (domain, allowed, added_by) values (?1, ?2, ?3)
+                 on conflict(domain) do update set allowed=?2, added_by=?3",
+                domain,
+                allowed,
+                added_by,
+            )
+            .execute(&mut conn)
+            .await?;
+        }
+
+        let mut cache = self.link_filter_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.domains.insert(domain, allowed);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(link_filter.domain = %domain))]
+    pub async fn remove_link_filter_domain(&self, domain: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        let removed = sqlx::query!("delete from link_filter_domain where domain = ?", domain)
+            .execute(&mut conn)
+            .await?
+            .rows_affected()
+            > 0;
+
+        let mut cache = self.link_filter_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.domains.remove(&domain);
+        }
+        Ok(removed)
+    }
+
+    #[tracing::instrument(skip_all, fields(link_filter.guild_id = %guild_id))]
+    pub async fn add_allowed_invite_guild(
+        &self,
+        guild_id: GuildId,
+        added_by: UserId,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        {
+            let guild_id = guild_id.0 as i64;
+            let added_by = added_by.0 as i64;
+            sqlx::query!(
+                "insert into link_filter_invite_guild (guild_id, added_by) values (?1, ?2)
+                 on conflict(guild_id) do update set added_by=?2",
+                guild_id,
+                added_by,
+            )
+            .execute(&mut conn)
+            .await?;
+        }
+
+        let mut cache = self.link_filter_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.allowed_invite_guilds.insert(guild_id);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(link_filter.guild_id = %guild_id))]
+    pub async fn remove_allowed_invite_guild(&self, guild_id: GuildId) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let removed = {
+            let guild_id = guild_id.0 as i64;
+            sqlx::query!("delete from link_filter_invite_guild where guild_id = ?", guild_id)
+                .execute(&mut conn)
+                .await?
+                .rows_affected()
+                > 0
+        };
+
+        let mut cache = self.link_filter_cache.write().await;
+        if let Some(cache) = cache.as_mut() {
+            cache.allowed_invite_guilds.remove(&guild_id);
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serenity::model::{
+    id::{ChannelId, RoleId, UserId},
+    Permissions,
+};
+
+use super::Db;
+
+#[derive(Debug)]
+pub struct Lockdown {
+    pub id: i64,
+    pub channel_id: ChannelId,
+    pub moderator: UserId,
+    pub reason: Option<String>,
+    pub start_time: DateTime<Utc>,
+    /// When the channel should automatically be unlocked, if at all.
+    pub end_time: Option<DateTime<Utc>>,
+    pub original_overwrites: Vec<LockdownOverwrite>,
+}
+
+/// A role permission overwrite of a channel, as it was before the channel got locked.
+#[derive(Debug, Clone)]
+pub struct LockdownOverwrite {
+    pub role_id: RoleId,
+    /// allowed and denied permissions, or `None` if the channel had no overwrite for this role.
+    pub permissions: Option<(Permissions, Permissions)>,
+}
+
+struct DbLockdownFields {
+    id: i64,
+    channel_id: i64,
+    moderator: i64,
+    reason: Option<String>,
+    start_time: NaiveDateTime,
+    end_time: Option<NaiveDateTime>,
+    /// Necessary field because it's part of the query output
+    #[allow(unused)]
+    active: bool,
+}
+
+impl DbLockdownFields {
+    fn into_lockdown(self, original_overwrites: Vec<LockdownOverwrite>) -> Lockdown {
+        Lockdown {
+            id: self.id,
+            channel_id: ChannelId(self.channel_id as u64),
+            moderator: UserId(self.moderator as u64),
+            reason: self.reason,
+            start_time: DateTime::from_utc(self.start_time, Utc),
+            end_time: self.end_time.map(|x| DateTime::from_utc(x, Utc)),
+            original_overwrites,
+        }
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all,
+        fields(lockdown.channel_id = %channel_id.0, lockdown.moderator = %moderator.0)
+    )]
+    pub async fn add_lockdown(
+        &self,
+        channel_id: ChannelId,
+        moderator: UserId,
+        reason: Option<String>,
+        start_time: DateTime<Utc>,
+        end_time: Option<DateTime<Utc>>,
+        original_overwrites: Vec<LockdownOverwrite>,
+    ) -> Result<Lockdown> {
+        let mut trans = self.pool.begin().await?;
+        let id = {
+            let channel_id = channel_id.0 as i64;
+            let moderator = moderator.0 as i64;
+            sqlx::query!(
+                "insert into lockdown (channel_id, moderator, reason, start_time, end_time, active) values (?, ?, ?, ?, ?, true)",
+                channel_id,
+                moderator,
+                reason,
+                start_time,
+                end_time,
+            )
+            .execute(&mut trans)
+            .await?
+            .last_insert_rowid()
+        };
+
+        for overwrite in original_overwrites.iter() {
+            let role_id = overwrite.role_id.0 as i64;
+            let allow = overwrite.permissions.map(|(allow, _)| allow.bits() as i64);
+            let deny = overwrite.permissions.map(|(_, deny)| deny.bits() as i64);
+            sqlx::query!(
+                "insert into lockdown_overwrite (lockdown, role_id, allow, deny) values (?, ?, ?, ?)",
+                id,
+                role_id,
+                allow,
+                deny,
+            )
+            .execute(&mut trans)
+            .await?;
+        }
+        trans.commit().await?;
+
+        Ok(Lockdown {
+            id,
+            channel_id,
+            moderator,
+            reason,
+            start_time,
+            end_time,
+            original_overwrites,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(lockdown.channel_id = %channel_id.0))]
+    pub async fn get_active_lockdown(&self, channel_id: ChannelId) -> Result<Option<Lockdown>> {
+        let mut conn = self.pool.acquire().await?;
+        let channel_id = channel_id.0 as i64;
+        let lockdown = sqlx::query_as!(
+            DbLockdownFields,
+            "select * from lockdown where channel_id = ? and active",
+            channel_id
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        match lockdown {
+            Some(lockdown) => {
+                let overwrites = self.get_lockdown_overwrites(lockdown.id).await?;
+                Ok(Some(lockdown.into_lockdown(overwrites)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_active_lockdowns(&self) -> Result<Vec<Lockdown>> {
+        let mut conn = self.pool.acquire().await?;
+        let lockdowns = sqlx::query_as!(DbLockdownFields, "select * from lockdown where active")
+            .fetch_all(&mut conn)
+            .await?;
+        let mut result = Vec::new();
+        for lockdown in lockdowns {
+            let overwrites = self.get_lockdown_overwrites(lockdown.id).await?;
+            result.push(lockdown.into_lockdown(overwrites));
+        }
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_newly_expired_lockdowns(&self) -> Result<Vec<Lockdown>> {
+        let mut conn = self.pool.acquire().await?;
+        let lockdowns = sqlx::query_as!(
+            DbLockdownFields,
+            "select * from lockdown
+             where cast(strftime('%s', end_time) as integer) < cast(strftime('%s', datetime('now')) as integer)
+               and active"
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        let mut result = Vec::new();
+        for lockdown in lockdowns {
+            let overwrites = self.get_lockdown_overwrites(lockdown.id).await?;
+            result.push(lockdown.into_lockdown(overwrites));
+        }
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn set_lockdown_inactive(&self, id: i64) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("update lockdown set active = false where id = ?", id)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_lockdown_overwrites(&self, lockdown_id: i64) -> Result<Vec<LockdownOverwrite>> {
+        let mut conn = self.pool.acquire().await?;
+        Ok(sqlx::query!(
+            "select role_id, allow, deny from lockdown_overwrite where lockdown = ?",
+            lockdown_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|x| LockdownOverwrite {
+            role_id: RoleId(x.role_id as u64),
+            permissions: x.allow.zip(x.deny).map(|(allow, deny)| {
+                (
+                    Permissions::from_bits_truncate(allow as u64),
+                    Permissions::from_bits_truncate(deny as u64),
+                )
+            }),
+        })
+        .collect())
+    }
+}
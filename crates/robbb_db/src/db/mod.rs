@@ -1,28 +1,43 @@
-use std::collections::HashSet;
 use std::sync::Arc;
 
 use anyhow::{bail, Context, Result};
 
-use serenity::model::id::UserId;
+use serenity::model::id::{ChannelId, UserId};
 use serenity::prelude::RwLock;
 use serenity::prelude::TypeMapKey;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
+pub mod auto_responder;
+pub mod auto_slowmode;
 pub mod blocklist;
 pub mod emoji_logging;
 pub mod fetch;
 pub mod fetch_field;
+pub mod fetch_stats;
+pub mod fetch_value_alias;
+pub mod highlight_digest;
 pub mod highlights;
+pub mod link_filter;
+pub mod lockdown;
 pub mod mod_action;
 pub mod mute;
+pub mod raid_mode;
 pub mod tag;
+pub mod tag_proposal;
+pub mod tag_usage;
+pub mod verification;
 
 #[derive(Debug)]
 pub struct Db {
     pool: SqlitePool,
     blocklist_cache: Arc<RwLock<Option<Vec<String>>>>,
     highlight_cache: RwLock<Option<highlights::HighlightsData>>,
-    tag_name_cache: RwLock<Option<HashSet<String>>>,
+    tag_name_cache: RwLock<Option<tag::TagNames>>,
+    tag_popularity_cache: RwLock<Option<HashMap<String, i64>>>,
+    auto_slowmode_cache: RwLock<Option<HashMap<ChannelId, auto_slowmode::AutoSlowmode>>>,
+    link_filter_cache: RwLock<Option<link_filter::LinkFilterData>>,
+    auto_responder_cache: RwLock<Option<Vec<auto_responder::AutoResponder>>>,
+    fetch_value_alias_cache: RwLock<Option<fetch_value_alias::FetchValueAliases>>,
 }
 
 impl TypeMapKey for Db {
@@ -37,6 +52,11 @@ impl Db {
             blocklist_cache: Arc::new(RwLock::new(None)),
             highlight_cache: RwLock::new(None),
             tag_name_cache: RwLock::new(None),
+            tag_popularity_cache: RwLock::new(None),
+            auto_slowmode_cache: RwLock::new(None),
+            link_filter_cache: RwLock::new(None),
+            auto_responder_cache: RwLock::new(None),
+            fetch_value_alias_cache: RwLock::new(None),
         })
     }
 
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serenity::model::id::UserId;
+
+use super::Db;
+
+#[derive(Debug)]
+pub struct RaidMode {
+    pub id: i64,
+    pub moderator: UserId,
+    pub action: RaidModeAction,
+    /// Verification level of the guild before raid mode was enabled, as discords numeric value.
+    pub previous_verification_level: i64,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+/// What happens to members that join while raid mode is active.
+#[derive(Debug, Eq, Copy, Clone, PartialEq, Hash, poise::ChoiceParameter)]
+pub enum RaidModeAction {
+    Quarantine,
+    Kick,
+}
+
+impl RaidModeAction {
+    pub fn from_i32(n: i32) -> Result<Self> {
+        match n {
+            0 => Ok(RaidModeAction::Quarantine),
+            1 => Ok(RaidModeAction::Kick),
+            _ => anyhow::bail!("Invalid raid mode action: {}", n),
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            RaidModeAction::Quarantine => 0,
+            RaidModeAction::Kick => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for RaidModeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RaidModeAction::Quarantine => write!(f, "Quarantine"),
+            RaidModeAction::Kick => write!(f, "Kick"),
+        }
+    }
+}
+
+struct DbRaidModeFields {
+    id: i64,
+    moderator: i64,
+    action: i64,
+    previous_verification_level: i64,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    /// Necessary field because it's part of the query output
+    #[allow(unused)]
+    active: bool,
+}
+
+impl DbRaidModeFields {
+    fn into_raid_mode(self) -> Result<RaidMode> {
+        Ok(RaidMode {
+            id: self.id,
+            moderator: UserId(self.moderator as u64),
+            action: RaidModeAction::from_i32(self.action as i32)?,
+            previous_verification_level: self.previous_verification_level,
+            start_time: DateTime::from_utc(self.start_time, Utc),
+            end_time: DateTime::from_utc(self.end_time, Utc),
+        })
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all,
+        fields(raid_mode.moderator = %moderator.0, raid_mode.action = %action)
+    )]
+    pub async fn start_raid_mode(
+        &self,
+        moderator: UserId,
+        action: RaidModeAction,
+        previous_verification_level: i64,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<RaidMode> {
+        let mut conn = self.pool.acquire().await?;
+        let id = {
+            let moderator = moderator.0 as i64;
+            let action = action.as_i32();
+            sqlx::query!(
+                "insert into raid_mode (moderator, action, previous_verification_level, start_time, end_time, active) values (?, ?, ?, ?, ?, true)",
+                moderator,
+                action,
+                previous_verification_level,
+                start_time,
+                end_time,
+            )
+            .execute(&mut conn)
+            .await?
+            .last_insert_rowid()
+        };
+        Ok(RaidMode { id, moderator, action, previous_verification_level, start_time, end_time })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_active_raid_mode(&self) -> Result<Option<RaidMode>> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query_as!(DbRaidModeFields, "select * from raid_mode where active")
+            .fetch_optional(&mut conn)
+            .await?
+            .map(|x| x.into_raid_mode())
+            .transpose()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_newly_expired_raid_modes(&self) -> Result<Vec<RaidMode>> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query_as!(
+            DbRaidModeFields,
+            "select * from raid_mode
+             where cast(strftime('%s', end_time) as integer) < cast(strftime('%s', datetime('now')) as integer)
+               and active"
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|x| x.into_raid_mode())
+        .collect::<Result<_>>()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn set_raid_mode_inactive(&self, id: i64) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("update raid_mode set active = false where id = ?", id)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all,
+        fields(raid_mode.id = %raid_mode_id, raid_mode.user = %user.0)
+    )]
+    pub async fn add_raid_mode_quarantined(&self, raid_mode_id: i64, user: UserId) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        sqlx::query!(
+            "insert or ignore into raid_mode_quarantine (raid_mode, usr) values (?, ?)",
+            raid_mode_id,
+            user
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the members that got quarantined while the given raid mode was active.
+    #[tracing::instrument(skip_all, fields(raid_mode.id = %raid_mode_id))]
+    pub async fn get_raid_mode_quarantined(&self, raid_mode_id: i64) -> Result<Vec<UserId>> {
+        let mut conn = self.pool.acquire().await?;
+        Ok(sqlx::query!("select usr from raid_mode_quarantine where raid_mode = ?", raid_mode_id)
+            .fetch_all(&mut conn)
+            .await?
+            .into_iter()
+            .map(|x| UserId(x.usr as u64))
+            .collect())
+    }
+}
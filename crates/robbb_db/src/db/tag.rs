@@ -1,5 +1,8 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serenity::model::id::UserId;
 
 use super::Db;
@@ -8,99 +11,403 @@ use super::Db;
 pub struct Tag {
     pub name: String,
     pub moderator: UserId,
+    /// Text of the tag, shown as the description of the embed for rich tags.
     pub content: String,
     pub official: bool,
     pub create_date: Option<DateTime<Utc>>,
+    pub category: Option<String>,
+    /// Additional embed content. Tags without it are sent as plain text embeds.
+    pub rich_content: Option<RichTagContent>,
+    /// Whether placeholders like `{user}` get substituted in the content.
+    pub templated: bool,
+}
+
+/// Structured embed content of a tag, stored as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RichTagContent {
+    pub title: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<RichTagField>,
+    pub image_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub color: Option<u32>,
+    #[serde(default)]
+    pub buttons: Vec<TagLinkButton>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RichTagField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagLinkButton {
+    pub label: String,
+    pub url: String,
+}
+
+fn parse_rich_content(value: Option<String>) -> Result<Option<RichTagContent>> {
+    value
+        .map(|x| serde_json::from_str(&x).context("Failed to deserialize rich tag content"))
+        .transpose()
+}
+
+/// Names of all tags and their aliases, used for autocompletion.
+#[derive(Debug, Clone, Default)]
+pub struct TagNames {
+    pub tags: HashSet<String>,
+    /// Maps each alias to the name of the tag it refers to.
+    pub aliases: HashMap<String, String>,
+}
+
+/// A tag matching a full-text search.
+#[derive(Debug, Clone)]
+pub struct TagSearchResult {
+    pub name: String,
+    /// Part of the content around the matching words, which are highlighted in bold.
+    pub snippet: String,
+}
+
+/// Turn user input into an FTS5 query, matching all of the words as prefixes.
+/// Quoting every word keeps any FTS5 syntax in the input from being interpreted.
+fn to_fts_query(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| format!("\"{}\"*", x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A past or current version of a tag's content.
+#[derive(Debug, Clone)]
+pub struct TagRevision {
+    pub tag: String,
+    pub revision: i64,
+    pub moderator: UserId,
+    pub content: String,
+    pub rich_content: Option<RichTagContent>,
+    pub create_date: DateTime<Utc>,
+}
+
+struct DbTagRevisionFields {
+    tag: String,
+    revision: i64,
+    moderator: i64,
+    content: String,
+    rich_content: Option<String>,
+    create_date: NaiveDateTime,
+}
+
+impl DbTagRevisionFields {
+    fn into_tag_revision(self) -> Result<TagRevision> {
+        Ok(TagRevision {
+            tag: self.tag,
+            revision: self.revision,
+            moderator: UserId(self.moderator as u64),
+            content: self.content,
+            rich_content: parse_rich_content(self.rich_content)?,
+            create_date: DateTime::from_utc(self.create_date, Utc),
+        })
+    }
 }
 
 impl Db {
+    /// Create or update a tag, storing the new content as a new revision of the tag.
     #[tracing::instrument(skip_all)]
     pub async fn set_tag(
         &self,
         moderator: UserId,
         name: String,
         content: String,
+        rich_content: Option<RichTagContent>,
         official: bool,
         create_date: Option<DateTime<Utc>>,
     ) -> Result<Tag> {
-        let mut conn = self.pool.acquire().await?;
+        let mut transaction = self.pool.begin().await?;
 
         let moderator_id = moderator.0 as i64;
+        let rich_content_json = rich_content.as_ref().map(serde_json::to_string).transpose()?;
         sqlx::query!(
-            "insert into tag (name, moderator, content, official, create_date) values (?, ?, ?, ?, ?)
-                on conflict(name) do update set moderator=?, content=?, official=?, create_date=?",
+            "insert into tag (name, moderator, content, rich_content, official, create_date)
+                values (?1, ?2, ?3, ?4, ?5, ?6)
+                on conflict(name) do update
+                set moderator=?2, content=?3, rich_content=?4, official=?5, create_date=?6",
             name,
             moderator_id,
             content,
+            rich_content_json,
             official,
             create_date,
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        let revision_date = create_date.unwrap_or_else(Utc::now);
+        sqlx::query!(
+            "insert into tag_revision (tag, revision, moderator, content, rich_content, create_date)
+             values (
+                ?1, (select coalesce(max(revision), 0) + 1 from tag_revision where tag=?1),
+                ?2, ?3, ?4, ?5
+             )",
+            name,
             moderator_id,
             content,
-            official,
-            create_date,
+            rich_content_json,
+            revision_date,
         )
-            .execute(&mut conn)
-            .await?;
+        .execute(&mut transaction)
+        .await?;
+        transaction.commit().await?;
 
         // Insert into the cache if there are already things in the cache.
         // If there aren't yet, then we don't care, as the cache will be filled with all values when it's read for the first time.
         if let Some(tag_names) = self.tag_name_cache.write().await.as_mut() {
-            tag_names.insert(name.clone());
+            tag_names.tags.insert(name.clone());
         }
 
-        Ok(Tag { name, moderator, content, official, create_date })
+        let existing = self.get_tag_by_name(&name).await?;
+        let category = existing.as_ref().and_then(|x| x.category.clone());
+        let templated = existing.map_or(false, |x| x.templated);
+        Ok(Tag {
+            name,
+            moderator,
+            content,
+            official,
+            create_date,
+            category,
+            rich_content,
+            templated,
+        })
     }
 
+    /// Get a tag by its name or one of its aliases.
     #[tracing::instrument(skip_all)]
     pub async fn get_tag(&self, name: &str) -> Result<Option<Tag>> {
+        if let Some(tag) = self.get_tag_by_name(name).await? {
+            return Ok(Some(tag));
+        }
+        let mut conn = self.pool.acquire().await?;
+        let alias_target = sqlx::query_scalar!("select tag from tag_alias where alias=?", name)
+            .fetch_optional(&mut conn)
+            .await?;
+        match alias_target {
+            Some(tag_name) => self.get_tag_by_name(&tag_name).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_tag_by_name(&self, name: &str) -> Result<Option<Tag>> {
         let mut conn = self.pool.acquire().await?;
-        Ok(sqlx::query!(
-            r#"select name as "name!", moderator, content, official, create_date from tag where name=? COLLATE NOCASE"#,
+        let tag = sqlx::query!(
+            r#"select name as "name!", moderator, content, official, create_date, category, rich_content, templated from tag where name=? COLLATE NOCASE"#,
             name
         )
         .fetch_optional(&mut conn)
-        .await?
-        .map(|x| {
-            let create_date = x
-                .create_date
-                .map(|date| chrono::DateTime::from_utc(date, chrono::Utc));
-            Tag {
-            name: x.name,
-            moderator: UserId(x.moderator as u64),
-            content: x.content,
-            official: x.official,
-            create_date,
-        }}))
+        .await?;
+        tag.map(|x| {
+            let create_date =
+                x.create_date.map(|date| chrono::DateTime::from_utc(date, chrono::Utc));
+            Ok(Tag {
+                name: x.name,
+                moderator: UserId(x.moderator as u64),
+                content: x.content,
+                official: x.official,
+                create_date,
+                category: x.category,
+                rich_content: parse_rich_content(x.rich_content)?,
+                templated: x.templated,
+            })
+        })
+        .transpose()
     }
 
+    /// Get all tags with their full content, ordered by name.
     #[tracing::instrument(skip_all)]
-    pub async fn delete_tag(&self, name: String) -> Result<()> {
+    pub async fn get_all_tags(&self) -> Result<Vec<Tag>> {
+        let mut conn = self.pool.acquire().await?;
+        let tags = sqlx::query!(
+            r#"select name as "name!", moderator, content, official, create_date, category, rich_content, templated from tag order by name"#,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        tags.into_iter()
+            .map(|x| {
+                Ok(Tag {
+                    name: x.name,
+                    moderator: UserId(x.moderator as u64),
+                    content: x.content,
+                    official: x.official,
+                    create_date: x.create_date.map(|date| DateTime::from_utc(date, Utc)),
+                    category: x.category,
+                    rich_content: parse_rich_content(x.rich_content)?,
+                    templated: x.templated,
+                })
+            })
+            .collect()
+    }
+
+    /// Search through the names and content of all tags, best matches first.
+    #[tracing::instrument(skip_all, fields(tag.search_query = %query))]
+    pub async fn search_tags(&self, query: &str, limit: i64) -> Result<Vec<TagSearchResult>> {
+        let fts_query = to_fts_query(query);
+        if fts_query.is_empty() {
+            return Ok(Vec::new());
+        }
         let mut conn = self.pool.acquire().await?;
+        // matches in the name weigh a lot more than matches in the content
+        let results = sqlx::query!(
+            r#"select name as "name!: String", snippet(tag_fts, 1, '**', '**', '…', 16) as "snippet!: String"
+             from tag_fts where tag_fts match ? order by bm25(tag_fts, 10.0, 1.0) limit ?"#,
+            fts_query,
+            limit
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(results
+            .into_iter()
+            .map(|x| TagSearchResult { name: x.name, snippet: x.snippet })
+            .collect())
+    }
+
+    /// Delete a tag, including all of its aliases.
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_tag(&self, name: String) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(r#"delete from tag where name=? COLLATE NOCASE"#, name)
-            .execute(&mut conn)
+            .execute(&mut transaction)
             .await?;
+        sqlx::query!(r#"delete from tag_alias where tag=? COLLATE NOCASE"#, name)
+            .execute(&mut transaction)
+            .await?;
+        transaction.commit().await?;
 
         if let Some(tag_names) = self.tag_name_cache.write().await.as_mut() {
-            tag_names.remove(&name);
+            tag_names.tags.remove(&name);
+            tag_names.aliases.retain(|_, tag| !tag.eq_ignore_ascii_case(&name));
         }
 
         Ok(())
     }
 
+    /// Get all revisions of a tag, oldest first.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_revisions(&self, name: &str) -> Result<Vec<TagRevision>> {
+        let mut conn = self.pool.acquire().await?;
+        let revisions = sqlx::query_as!(
+            DbTagRevisionFields,
+            "select tag, revision, moderator, content, rich_content, create_date
+             from tag_revision where tag=? order by revision",
+            name
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        revisions.into_iter().map(|x| x.into_tag_revision()).collect()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_revision(&self, name: &str, revision: i64) -> Result<Option<TagRevision>> {
+        let mut conn = self.pool.acquire().await?;
+        let revision = sqlx::query_as!(
+            DbTagRevisionFields,
+            "select tag, revision, moderator, content, rich_content, create_date
+             from tag_revision where tag=? and revision=?",
+            name,
+            revision
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        revision.map(|x| x.into_tag_revision()).transpose()
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn set_tag_category(&self, name: &str, category: Option<String>) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("update tag set category=? where name=? COLLATE NOCASE", category, name)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn set_tag_templated(&self, name: &str, templated: bool) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("update tag set templated=? where name=? COLLATE NOCASE", templated, name)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn add_tag_alias(&self, alias: String, tag: String) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("insert into tag_alias (alias, tag) values (?, ?)", alias, tag)
+            .execute(&mut conn)
+            .await?;
+
+        if let Some(tag_names) = self.tag_name_cache.write().await.as_mut() {
+            tag_names.aliases.insert(alias, tag);
+        }
+        Ok(())
+    }
+
+    /// Remove an alias. Returns false if there was no such alias.
+    #[tracing::instrument(skip_all)]
+    pub async fn remove_tag_alias(&self, alias: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let result =
+            sqlx::query!("delete from tag_alias where alias=?", alias).execute(&mut conn).await?;
+
+        if let Some(tag_names) = self.tag_name_cache.write().await.as_mut() {
+            tag_names.aliases.retain(|x, _| !x.eq_ignore_ascii_case(alias));
+        }
+        Ok(result.rows_affected() > 0)
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn list_tags(&self) -> Result<Vec<String>> {
+        Ok(self.list_tag_names().await?.tags.into_iter().collect())
+    }
+
+    /// Get the names of all tags together with their category.
+    #[tracing::instrument(skip_all)]
+    pub async fn list_tags_with_category(
+        &self,
+        official_only: bool,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let mut conn = self.pool.acquire().await?;
+        let tags = sqlx::query!(
+            r#"select name as "name!", category from tag where official or not ? order by name"#,
+            official_only
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(tags.into_iter().map(|x| (x.name, x.category)).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn list_tag_names(&self) -> Result<TagNames> {
         let tag_name_cache = self.tag_name_cache.read().await;
         if let Some(tag_names) = tag_name_cache.as_ref() {
-            Ok(tag_names.clone().into_iter().collect())
+            Ok(tag_names.clone())
         } else {
             std::mem::drop(tag_name_cache);
             let mut conn = self.pool.acquire().await?;
-            let tag_names = sqlx::query_scalar(r#"select name as "name!" from tag"#)
+            let tags: Vec<String> = sqlx::query_scalar(r#"select name as "name!" from tag"#)
                 .fetch_all(&mut conn)
                 .await?;
+            let aliases =
+                sqlx::query!("select alias, tag from tag_alias").fetch_all(&mut conn).await?;
+            let tag_names = TagNames {
+                tags: tags.into_iter().collect(),
+                aliases: aliases.into_iter().map(|x| (x.alias, x.tag)).collect(),
+            };
 
             let mut tag_name_cache = self.tag_name_cache.write().await;
-            let _ = tag_name_cache.insert(tag_names.clone().into_iter().collect());
+            let _ = tag_name_cache.insert(tag_names.clone());
 
             Ok(tag_names)
         }
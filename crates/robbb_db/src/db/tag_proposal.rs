@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serenity::model::id::UserId;
+
+use super::Db;
+
+/// A tag submitted by a member, waiting for a moderator to review it.
+#[derive(Debug, Clone)]
+pub struct TagProposal {
+    pub id: i64,
+    pub name: String,
+    pub content: String,
+    pub author: UserId,
+    pub create_date: DateTime<Utc>,
+}
+
+struct DbTagProposalFields {
+    id: i64,
+    name: String,
+    content: String,
+    author: i64,
+    create_date: NaiveDateTime,
+}
+
+impl DbTagProposalFields {
+    fn into_tag_proposal(self) -> TagProposal {
+        TagProposal {
+            id: self.id,
+            name: self.name,
+            content: self.content,
+            author: UserId(self.author as u64),
+            create_date: DateTime::from_utc(self.create_date, Utc),
+        }
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all)]
+    pub async fn add_tag_proposal(
+        &self,
+        name: String,
+        content: String,
+        author: UserId,
+    ) -> Result<TagProposal> {
+        let mut conn = self.pool.acquire().await?;
+        let author_id = author.0 as i64;
+        let create_date = Utc::now();
+        let id = sqlx::query!(
+            "insert into tag_proposal (name, content, author, create_date) values (?, ?, ?, ?)",
+            name,
+            content,
+            author_id,
+            create_date,
+        )
+        .execute(&mut conn)
+        .await?
+        .last_insert_rowid();
+        Ok(TagProposal { id, name, content, author, create_date })
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_proposal(&self, id: i64) -> Result<Option<TagProposal>> {
+        let mut conn = self.pool.acquire().await?;
+        let proposal = sqlx::query_as!(
+            DbTagProposalFields,
+            "select id, name, content, author, create_date from tag_proposal where id=?",
+            id
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        Ok(proposal.map(|x| x.into_tag_proposal()))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_tag_proposal(&self, id: i64) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!("delete from tag_proposal where id=?", id).execute(&mut conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use serenity::model::id::ChannelId;
+
+use super::Db;
+
+/// How often a tag has been used.
+#[derive(Debug, Clone)]
+pub struct TagUsageStats {
+    pub tag: String,
+    /// Uses within the requested time span.
+    pub uses: i64,
+    /// The last day the tag was used on, if it ever was.
+    pub last_used: Option<NaiveDate>,
+}
+
+impl Db {
+    #[tracing::instrument(skip_all, fields(tag.name = %tag))]
+    pub async fn record_tag_usage(&self, tag: &str, channel_id: ChannelId) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let day = Utc::now().naive_utc().date();
+        let channel_id = channel_id.0 as i64;
+        sqlx::query!(
+            "insert into tag_usage (tag, day, channel_id, count) values (?1, ?2, ?3, 1)
+             on conflict(tag, day, channel_id) do update set count = count + 1",
+            tag,
+            day,
+            channel_id,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        if let Some(popularity) = self.tag_popularity_cache.write().await.as_mut() {
+            *popularity.entry(tag.to_lowercase()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    /// Get the usage of every existing tag since the given day.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_usage_stats(&self, since: NaiveDate) -> Result<Vec<TagUsageStats>> {
+        let mut conn = self.pool.acquire().await?;
+        let stats = sqlx::query!(
+            r#"select
+                name as "name!",
+                (select coalesce(sum(count), 0) from tag_usage where tag_usage.tag = tag.name and day >= ?) as "uses!: i64",
+                (select max(day) from tag_usage where tag_usage.tag = tag.name) as "last_used: NaiveDate"
+             from tag"#,
+            since
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(stats
+            .into_iter()
+            .map(|x| TagUsageStats { tag: x.name, uses: x.uses, last_used: x.last_used })
+            .collect())
+    }
+
+    /// Get how often a tag has been used in each channel since the given day, most used first.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_usage_by_channel(
+        &self,
+        tag: &str,
+        since: NaiveDate,
+    ) -> Result<Vec<(ChannelId, i64)>> {
+        let mut conn = self.pool.acquire().await?;
+        let usage = sqlx::query!(
+            r#"select channel_id, sum(count) as "uses!: i64" from tag_usage
+             where tag = ? and day >= ? group by channel_id order by sum(count) desc"#,
+            tag,
+            since
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        Ok(usage.into_iter().map(|x| (ChannelId(x.channel_id as u64), x.uses)).collect())
+    }
+
+    /// Get the total amount of uses per tag, keyed by the lowercase tag name.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tag_popularity(&self) -> Result<HashMap<String, i64>> {
+        let popularity_cache = self.tag_popularity_cache.read().await;
+        if let Some(popularity) = popularity_cache.as_ref() {
+            Ok(popularity.clone())
+        } else {
+            std::mem::drop(popularity_cache);
+            let mut conn = self.pool.acquire().await?;
+            let usage = sqlx::query!(
+                r#"select tag, sum(count) as "uses!: i64" from tag_usage group by tag"#
+            )
+            .fetch_all(&mut conn)
+            .await?;
+            let popularity: HashMap<_, _> =
+                usage.into_iter().map(|x| (x.tag.to_lowercase(), x.uses)).collect();
+
+            let mut popularity_cache = self.tag_popularity_cache.write().await;
+            let _ = popularity_cache.insert(popularity.clone());
+            Ok(popularity)
+        }
+    }
+}
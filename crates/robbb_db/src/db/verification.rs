@@ -0,0 +1,97 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serenity::model::id::UserId;
+
+use super::Db;
+
+/// A member that joined, but hasn't passed the verification gate yet.
+#[derive(Debug)]
+pub struct PendingVerification {
+    pub user: UserId,
+    pub join_time: DateTime<Utc>,
+    /// Time at which the member gets kicked if they haven't verified by then.
+    pub deadline: DateTime<Utc>,
+}
+
+struct DbPendingVerificationFields {
+    usr: i64,
+    join_time: NaiveDateTime,
+    deadline: NaiveDateTime,
+}
+
+impl DbPendingVerificationFields {
+    fn into_pending_verification(self) -> PendingVerification {
+        PendingVerification {
+            user: UserId(self.usr as u64),
+            join_time: DateTime::from_utc(self.join_time, Utc),
+            deadline: DateTime::from_utc(self.deadline, Utc),
+        }
+    }
+}
+
+impl Db {
+    #[tracing::instrument(skip_all, fields(verification.user = %user.0))]
+    pub async fn add_pending_verification(
+        &self,
+        user: UserId,
+        join_time: DateTime<Utc>,
+        deadline: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        sqlx::query!(
+            "insert into pending_verification (usr, join_time, deadline) values (?1, ?2, ?3)
+             on conflict(usr) do update set join_time=?2, deadline=?3",
+            user,
+            join_time,
+            deadline
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(verification.user = %user.0))]
+    pub async fn get_pending_verification(
+        &self,
+        user: UserId,
+    ) -> Result<Option<PendingVerification>> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        Ok(sqlx::query_as!(
+            DbPendingVerificationFields,
+            "select * from pending_verification where usr = ?",
+            user
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(|x| x.into_pending_verification()))
+    }
+
+    /// Remove a pending verification. Returns true if there was one.
+    #[tracing::instrument(skip_all, fields(verification.user = %user.0))]
+    pub async fn remove_pending_verification(&self, user: UserId) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        let user = user.0 as i64;
+        Ok(sqlx::query!("delete from pending_verification where usr = ?", user)
+            .execute(&mut conn)
+            .await?
+            .rows_affected()
+            > 0)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_expired_pending_verifications(&self) -> Result<Vec<PendingVerification>> {
+        let mut conn = self.pool.acquire().await?;
+        Ok(sqlx::query_as!(
+            DbPendingVerificationFields,
+            "select * from pending_verification
+             where cast(strftime('%s', deadline) as integer) < cast(strftime('%s', datetime('now')) as integer)"
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|x| x.into_pending_verification())
+        .collect())
+    }
+}
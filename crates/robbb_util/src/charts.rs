@@ -0,0 +1,193 @@
+//! Rendering of simple bar and pie charts as PNG images.
+//! The font is bundled into the binary, so this works without any fonts installed on the server.
+
+use std::f64::consts::PI;
+
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use plotters::style::{FontStyle, Palette99, RGBColor};
+
+static FONT: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+const FONT_FAMILY: &str = "sans-serif";
+
+/// Same as the background of embeds in discords dark theme, so charts blend in.
+const BACKGROUND_COLOR: RGBColor = RGBColor(0x2f, 0x31, 0x36);
+const TEXT_COLOR: RGBColor = RGBColor(0xdc, 0xdd, 0xde);
+
+const WIDTH: u32 = 800;
+const MARGIN: i32 = 20;
+const TITLE_HEIGHT: i32 = 60;
+const ROW_HEIGHT: i32 = 44;
+const BAR_HEIGHT: i32 = 32;
+const LABEL_WIDTH: i32 = 240;
+const VALUE_WIDTH: i32 = 90;
+const PIE_RADIUS: i32 = 200;
+const MAX_LABEL_LENGTH: usize = 24;
+
+lazy_static::lazy_static! {
+    static ref FONT_REGISTERED: bool =
+        plotters::style::register_font(FONT_FAMILY, FontStyle::Normal, FONT).is_ok();
+}
+
+/// The kind of chart to render some statistics as.
+#[derive(Debug, Eq, Copy, Clone, PartialEq, Hash, poise::ChoiceParameter)]
+pub enum ChartKind {
+    Bar,
+    Pie,
+}
+
+/// Render labeled values as a PNG image of the given kind of chart.
+/// The `unit` is appended to the values shown next to the bars of a bar chart, i.e. `"%"`.
+pub fn render_chart(
+    kind: ChartKind,
+    title: &str,
+    unit: &str,
+    data: &[(String, f64)],
+) -> Result<Vec<u8>> {
+    if data.is_empty() {
+        bail!("There is no data to render a chart of");
+    }
+    match kind {
+        ChartKind::Bar => render_bar_chart(title, unit, data),
+        ChartKind::Pie => render_pie_chart(title, data),
+    }
+}
+
+fn render_bar_chart(title: &str, unit: &str, data: &[(String, f64)]) -> Result<Vec<u8>> {
+    let height = TITLE_HEIGHT + data.len() as i32 * ROW_HEIGHT + MARGIN;
+    render_png(WIDTH, height as u32, |root| {
+        draw_title(root, title)?;
+        let max_value = data.iter().map(|(_, value)| *value).fold(0f64, f64::max);
+        let bar_start = MARGIN + LABEL_WIDTH;
+        let max_bar_width = WIDTH as i32 - bar_start - VALUE_WIDTH - MARGIN;
+        for (idx, (label, value)) in data.iter().enumerate() {
+            let top = TITLE_HEIGHT + idx as i32 * ROW_HEIGHT;
+            let center = top + BAR_HEIGHT / 2;
+            let bar_width = if max_value > 0f64 {
+                (value / max_value * max_bar_width as f64).round() as i32
+            } else {
+                0
+            };
+            root.draw_text(
+                &shorten_label(label),
+                &text_style(18).pos(Pos::new(HPos::Right, VPos::Center)),
+                (bar_start - 10, center),
+            )?;
+            root.draw(&Rectangle::new(
+                [(bar_start, top), (bar_start + bar_width.max(1), top + BAR_HEIGHT)],
+                Palette99::pick(idx).filled(),
+            ))?;
+            root.draw_text(
+                &format!("{}{}", format_value(*value), unit),
+                &text_style(18).pos(Pos::new(HPos::Left, VPos::Center)),
+                (bar_start + bar_width + 8, center),
+            )?;
+        }
+        Ok(())
+    })
+}
+
+fn render_pie_chart(title: &str, data: &[(String, f64)]) -> Result<Vec<u8>> {
+    let total: f64 = data.iter().map(|(_, value)| value).sum();
+    if total <= 0f64 {
+        bail!("There is no data to render a chart of");
+    }
+    let legend_height = TITLE_HEIGHT + data.len() as i32 * ROW_HEIGHT;
+    let pie_height = TITLE_HEIGHT + 2 * PIE_RADIUS + MARGIN;
+    let height = legend_height.max(pie_height) + MARGIN;
+    render_png(WIDTH, height as u32, |root| {
+        draw_title(root, title)?;
+        let center = (MARGIN + PIE_RADIUS, TITLE_HEIGHT + PIE_RADIUS);
+        let legend_start = MARGIN * 3 + PIE_RADIUS * 2;
+        let mut angle = -PI / 2f64;
+        for (idx, (label, value)) in data.iter().enumerate() {
+            let color = Palette99::pick(idx);
+            let sweep = value / total * 2f64 * PI;
+            // approximate the arc with one point per degree
+            let steps = (sweep.to_degrees().ceil() as usize).max(1);
+            let arc = (0..=steps).map(|step| {
+                let point_angle = angle + sweep * step as f64 / steps as f64;
+                (
+                    center.0 + (PIE_RADIUS as f64 * point_angle.cos()).round() as i32,
+                    center.1 + (PIE_RADIUS as f64 * point_angle.sin()).round() as i32,
+                )
+            });
+            let points = std::iter::once(center).chain(arc).collect_vec();
+            root.draw(&Polygon::new(points, color.filled()))?;
+            angle += sweep;
+
+            let row_center = TITLE_HEIGHT + idx as i32 * ROW_HEIGHT + BAR_HEIGHT / 2;
+            root.draw(&Rectangle::new(
+                [(legend_start, row_center - 9), (legend_start + 18, row_center + 9)],
+                color.filled(),
+            ))?;
+            root.draw_text(
+                &format!("{} ({:.1}%)", shorten_label(label), value / total * 100f64),
+                &text_style(18).pos(Pos::new(HPos::Left, VPos::Center)),
+                (legend_start + 28, row_center),
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Draw onto a canvas of the given size and encode the result as a PNG.
+fn render_png(
+    width: u32,
+    height: u32,
+    draw: impl FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<()>,
+) -> Result<Vec<u8>> {
+    if !*FONT_REGISTERED {
+        bail!("Failed to load the chart font");
+    }
+
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+        root.fill(&BACKGROUND_COLOR)?;
+        draw(&root)?;
+        root.present()?;
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&buffer)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+fn draw_title(root: &DrawingArea<BitMapBackend, Shift>, title: &str) -> Result<()> {
+    root.draw_text(
+        title,
+        &text_style(26).pos(Pos::new(HPos::Left, VPos::Center)),
+        (MARGIN, TITLE_HEIGHT / 2),
+    )?;
+    Ok(())
+}
+
+fn text_style(size: u32) -> TextStyle<'static> {
+    (FONT_FAMILY, size).into_font().color(&TEXT_COLOR)
+}
+
+fn shorten_label(label: &str) -> String {
+    if label.chars().count() > MAX_LABEL_LENGTH {
+        format!("{}…", label.chars().take(MAX_LABEL_LENGTH - 1).collect::<String>())
+    } else {
+        label.to_string()
+    }
+}
+
+/// Format a value without decimal places if it's a whole number.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0f64 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
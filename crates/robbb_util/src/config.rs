@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use poise::serenity_prelude::{ChannelId, CreateEmbed, GuildId, RoleId, TypeMapKey};
 use serenity::client;
@@ -6,7 +6,7 @@ use serenity::client;
 use crate::{
     extensions::GuildIdExt,
     log_error,
-    util::{parse_required_env_var, required_env_var},
+    util::{parse_optional_env_var, parse_required_env_var, required_env_var},
 };
 
 #[derive(Debug)]
@@ -17,6 +17,9 @@ pub struct Config {
     pub role_mod: RoleId,
     pub role_helper: RoleId,
     pub role_mute: RoleId,
+    pub role_quarantine: Option<RoleId>,
+    /// Role that members get once they pass verification.
+    pub role_verified: Option<RoleId>,
     pub roles_color: Vec<RoleId>,
 
     pub category_mod_private: ChannelId,
@@ -30,10 +33,51 @@ pub struct Config {
     pub channel_tech_support: ChannelId,
     pub channel_mod_polls: ChannelId,
     pub channel_attachment_dump: Option<ChannelId>,
+    /// Channel with the verification button. Verification is only enabled if this and
+    /// [Config::role_quarantine] are set.
+    pub channel_verification: Option<ChannelId>,
+    /// Channel where tag proposals by members get reviewed. Proposals are disabled if unset.
+    pub channel_tag_review: Option<ChannelId>,
 
     pub attachment_cache_path: PathBuf,
     pub attachment_cache_max_size: usize,
 
+    /// Amount of joins within [Config::raid_join_window] that is considered a raid.
+    pub raid_join_threshold: usize,
+    /// Amount of joins of young accounts within [Config::raid_join_window] that is considered a raid.
+    pub raid_young_join_threshold: usize,
+    /// Accounts younger than this count as young accounts for raid detection.
+    pub raid_young_account_age: chrono::Duration,
+    pub raid_join_window: std::time::Duration,
+    /// Whether raid mode should automatically be enabled when a raid is detected.
+    pub raid_auto_raidmode: bool,
+    pub raid_mode_duration: std::time::Duration,
+
+    /// What happens to users that post blocked links or invites.
+    pub link_filter_action: LinkFilterAction,
+    /// Accounts younger than this get banned instead of muted by [LinkFilterAction::Ban].
+    pub link_filter_new_account_age: chrono::Duration,
+    pub link_filter_mute_duration: std::time::Duration,
+
+    /// Accounts younger than this have to answer [Config::verification_question] to verify.
+    pub verification_young_account_age: chrono::Duration,
+    /// Members that don't verify within this time get kicked.
+    pub verification_timeout: std::time::Duration,
+    pub verification_question: Option<String>,
+    /// Accepted answers to [Config::verification_question], compared case-insensitively.
+    pub verification_answers: Vec<String>,
+
+    /// Users don't get highlight notifications for channels they wrote in within this time.
+    pub highlight_active_window: std::time::Duration,
+    /// Minimum time between two highlight notifications to the same user.
+    pub highlight_cooldown: std::time::Duration,
+
+    /// Whether fetch values that are known aliases get stored as their canonical value.
+    pub fetch_canonicalize_on_write: bool,
+
+    /// Named groups of channels that can be locked down together.
+    pub lockdown_presets: HashMap<String, Vec<ChannelId>>,
+
     pub time_started: chrono::DateTime<chrono::Utc>,
 }
 
@@ -45,6 +89,8 @@ impl Config {
             role_mod: RoleId(parse_required_env_var("ROLE_MOD")?),
             role_helper: RoleId(parse_required_env_var("ROLE_HELPER")?),
             role_mute: RoleId(parse_required_env_var("ROLE_MUTE")?),
+            role_quarantine: parse_optional_env_var("ROLE_QUARANTINE")?.map(RoleId),
+            role_verified: parse_optional_env_var("ROLE_VERIFIED")?.map(RoleId),
             roles_color: required_env_var("ROLES_COLOR")?
                 .split(',')
                 .map(|x| Ok(RoleId(x.trim().parse()?)))
@@ -62,12 +108,68 @@ impl Config {
             channel_attachment_dump: parse_required_env_var("CHANNEL_ATTACHMENT_DUMP")
                 .map(ChannelId)
                 .ok(),
+            channel_verification: parse_optional_env_var("CHANNEL_VERIFICATION")?.map(ChannelId),
+            channel_tag_review: parse_required_env_var("CHANNEL_TAG_REVIEW").map(ChannelId).ok(),
             attachment_cache_path: parse_required_env_var("ATTACHMENT_CACHE_PATH")?,
             attachment_cache_max_size: parse_required_env_var("ATTACHMENT_CACHE_MAX_SIZE")?,
+            raid_join_threshold: parse_optional_env_var("RAID_JOIN_THRESHOLD")?.unwrap_or(10),
+            raid_young_join_threshold: parse_optional_env_var("RAID_YOUNG_JOIN_THRESHOLD")?
+                .unwrap_or(5),
+            raid_young_account_age: chrono::Duration::days(
+                parse_optional_env_var("RAID_YOUNG_ACCOUNT_DAYS")?.unwrap_or(7),
+            ),
+            raid_join_window: std::time::Duration::from_secs(
+                parse_optional_env_var("RAID_JOIN_WINDOW_SECS")?.unwrap_or(60),
+            ),
+            raid_auto_raidmode: parse_optional_env_var("RAID_AUTO_RAIDMODE")?.unwrap_or(false),
+            raid_mode_duration: std::time::Duration::from_secs(
+                parse_optional_env_var("RAID_MODE_DURATION_SECS")?.unwrap_or(60 * 30),
+            ),
+            link_filter_action: parse_optional_env_var("LINK_FILTER_ACTION")?
+                .unwrap_or(LinkFilterAction::Delete),
+            link_filter_new_account_age: chrono::Duration::days(
+                parse_optional_env_var("LINK_FILTER_NEW_ACCOUNT_DAYS")?.unwrap_or(7),
+            ),
+            link_filter_mute_duration: std::time::Duration::from_secs(
+                parse_optional_env_var("LINK_FILTER_MUTE_SECS")?.unwrap_or(60 * 60 * 24),
+            ),
+            verification_young_account_age: chrono::Duration::days(
+                parse_optional_env_var("VERIFICATION_YOUNG_ACCOUNT_DAYS")?.unwrap_or(7),
+            ),
+            verification_timeout: std::time::Duration::from_secs(
+                parse_optional_env_var("VERIFICATION_TIMEOUT_SECS")?.unwrap_or(60 * 60),
+            ),
+            verification_question: required_env_var("VERIFICATION_QUESTION")
+                .ok()
+                .filter(|x| !x.trim().is_empty()),
+            verification_answers: required_env_var("VERIFICATION_ANSWERS")
+                .map(|x| {
+                    x.split(',')
+                        .map(|x| x.trim().to_lowercase())
+                        .filter(|x| !x.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            highlight_active_window: std::time::Duration::from_secs(
+                parse_optional_env_var("HIGHLIGHT_ACTIVE_WINDOW_SECS")?.unwrap_or(60 * 5),
+            ),
+            highlight_cooldown: std::time::Duration::from_secs(
+                parse_optional_env_var("HIGHLIGHT_COOLDOWN_SECS")?.unwrap_or(60),
+            ),
+            fetch_canonicalize_on_write: parse_optional_env_var("FETCH_CANONICALIZE_ON_WRITE")?
+                .unwrap_or(false),
+            lockdown_presets: required_env_var("LOCKDOWN_PRESETS")
+                .map(|x| parse_lockdown_presets(&x))
+                .unwrap_or_else(|_| Ok(HashMap::new()))?,
             time_started: chrono::Utc::now(),
         })
     }
 
+    /// Whether new members have to go through the verification gate.
+    pub fn verification_enabled(&self) -> bool {
+        self.channel_verification.is_some() && self.role_quarantine.is_some()
+    }
+
     pub async fn log_bot_action<F>(&self, ctx: &client::Context, build_embed: F)
     where
         F: FnOnce(&mut CreateEmbed) + Send + Sync,
@@ -91,6 +193,48 @@ impl Config {
     //}
 }
 
+/// What happens to users that post a blocked link or invite.
+/// The message itself always gets deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkFilterAction {
+    Delete,
+    /// Mute the user for [Config::link_filter_mute_duration].
+    Mute,
+    /// Ban accounts younger than [Config::link_filter_new_account_age], mute everyone else.
+    Ban,
+}
+
+impl std::str::FromStr for LinkFilterAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "delete" => Ok(LinkFilterAction::Delete),
+            "mute" => Ok(LinkFilterAction::Mute),
+            "ban" => Ok(LinkFilterAction::Ban),
+            _ => anyhow::bail!("Invalid link filter action: {}", s),
+        }
+    }
+}
+
+/// Parse lockdown presets in the format `name:channel_id,channel_id;other_name:channel_id`
+fn parse_lockdown_presets(value: &str) -> anyhow::Result<HashMap<String, Vec<ChannelId>>> {
+    value
+        .split(';')
+        .filter(|x| !x.trim().is_empty())
+        .map(|preset| {
+            let (name, channels) = preset
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid lockdown preset: {}", preset))?;
+            let channels = channels
+                .split(',')
+                .map(|x| Ok(ChannelId(x.trim().parse()?)))
+                .collect::<anyhow::Result<_>>()?;
+            Ok((name.trim().to_string(), channels))
+        })
+        .collect()
+}
+
 impl TypeMapKey for Config {
     type Value = Arc<Config>;
 }
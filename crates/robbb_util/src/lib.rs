@@ -1,9 +1,12 @@
+pub mod charts;
 pub mod collect_interaction;
 pub mod config;
 pub mod embeds;
 pub mod extensions;
 pub mod modal;
 pub mod prelude;
+pub mod tag_archive;
+pub mod tag_template;
 pub mod util;
 
 use std::sync::Arc;
@@ -0,0 +1,331 @@
+//! Converting tags from and to a tarball of Markdown files.
+//!
+//! Every tag is stored as its own file, with its metadata as front matter:
+//!
+//! ```text
+//! ---
+//! name: "rice"
+//! aliases: ["ricing"]
+//! category: "Customization"
+//! official: true
+//! templated: false
+//! author: 1234567890
+//! ---
+//! The content of the tag
+//! ```
+//!
+//! Values are written as JSON, but plain unquoted text is accepted as well,
+//! to make editing the files by hand less annoying.
+//! Everything but the name is optional. Tags without `official: true` are community tags.
+
+use std::collections::HashSet;
+use std::io::Read;
+
+use anyhow::{Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use itertools::Itertools;
+use poise::serenity_prelude::UserId;
+use robbb_db::tag::RichTagContent;
+
+/// Directory inside of the archive that contains the tag files.
+const ARCHIVE_DIR: &str = "tags";
+
+/// Maximum amount of tag files read from an archive.
+const MAX_ARCHIVE_ENTRIES: usize = 2000;
+
+/// Maximum size of an archive after decompressing it.
+const MAX_UNPACKED_SIZE: u64 = 20 * 1024 * 1024;
+
+/// A tag as it's represented in a Markdown file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagFile {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub category: Option<String>,
+    pub official: bool,
+    /// Whether placeholders get substituted in the content, see [crate::tag_template].
+    pub templated: bool,
+    pub author: Option<UserId>,
+    pub content: String,
+    pub rich_content: Option<RichTagContent>,
+}
+
+impl TagFile {
+    pub fn to_markdown(&self) -> Result<String> {
+        let mut text = String::from("---\n");
+        text.push_str(&format!("name: {}\n", serde_json::to_string(&self.name)?));
+        if !self.aliases.is_empty() {
+            text.push_str(&format!("aliases: {}\n", serde_json::to_string(&self.aliases)?));
+        }
+        if let Some(category) = &self.category {
+            text.push_str(&format!("category: {}\n", serde_json::to_string(category)?));
+        }
+        text.push_str(&format!("official: {}\n", self.official));
+        text.push_str(&format!("templated: {}\n", self.templated));
+        if let Some(author) = self.author {
+            text.push_str(&format!("author: {}\n", author.0));
+        }
+        if let Some(rich_content) = &self.rich_content {
+            text.push_str(&format!("rich_content: {}\n", serde_json::to_string(rich_content)?));
+        }
+        text.push_str("---\n");
+        text.push_str(self.content.trim_end());
+        text.push('\n');
+        Ok(text)
+    }
+
+    pub fn from_markdown(text: &str) -> Result<TagFile> {
+        let text = text.replace("\r\n", "\n");
+        let rest = text.strip_prefix("---\n").context("File doesn't start with front matter")?;
+        let (front_matter, content) = match rest.split_once("\n---\n") {
+            Some(parts) => parts,
+            None => (rest.strip_suffix("\n---").context("Front matter is never closed")?, ""),
+        };
+
+        let mut name = None;
+        let mut tag_file = TagFile {
+            name: String::new(),
+            aliases: Vec::new(),
+            category: None,
+            official: false,
+            templated: false,
+            author: None,
+            content: content.trim_end().to_string(),
+            rich_content: None,
+        };
+        for line in front_matter.lines().filter(|x| !x.trim().is_empty()) {
+            let (key, value) = line.split_once(':').context(format!("Invalid line: {}", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(parse_text(value)?),
+                "aliases" => tag_file.aliases = parse_list(value)?,
+                "category" => tag_file.category = Some(parse_text(value)?),
+                "official" => {
+                    tag_file.official = value.parse().context("official must be true or false")?
+                }
+                "templated" => {
+                    tag_file.templated = value.parse().context("templated must be true or false")?
+                }
+                "author" => {
+                    let author = parse_text(value)?.parse().context("author must be a user id")?;
+                    tag_file.author = Some(UserId(author));
+                }
+                "rich_content" => {
+                    let rich_content =
+                        serde_json::from_str(value).context("Invalid rich_content")?;
+                    tag_file.rich_content = Some(rich_content);
+                }
+                other => anyhow::bail!("Unknown front matter key {}", other),
+            }
+        }
+        tag_file.name = name.context("Missing name in front matter")?;
+        tag_file.aliases.retain(|x| !x.is_empty());
+        tag_file.category = tag_file.category.filter(|x| !x.is_empty());
+        if tag_file.name.is_empty() {
+            anyhow::bail!("The name can't be empty");
+        }
+        Ok(tag_file)
+    }
+}
+
+/// Pack the given tags into a gzipped tarball, with one Markdown file per tag.
+pub fn write_archive(tags: &[TagFile]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut used_file_names = HashSet::new();
+    for tag in tags {
+        let base_name = file_name_for(&tag.name);
+        let mut file_name = base_name.clone();
+        // names that only differ in characters that get replaced need distinct files
+        for idx in 2.. {
+            if used_file_names.insert(file_name.to_ascii_lowercase()) {
+                break;
+            }
+            file_name = format!("{}-{}", base_name, idx);
+        }
+
+        let markdown = tag.to_markdown()?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(markdown.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(chrono::Utc::now().timestamp() as u64);
+        let path = format!("{}/{}.md", ARCHIVE_DIR, file_name);
+        builder.append_data(&mut header, path, markdown.as_bytes())?;
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// Read all Markdown files from a tarball, which may be gzipped.
+/// Files in any directory are read, everything that isn't a Markdown file is ignored.
+pub fn read_archive(data: &[u8]) -> Result<Vec<TagFile>> {
+    let reader: Box<dyn Read + '_> = if data.starts_with(&[0x1f, 0x8b]) {
+        Box::new(GzDecoder::new(data))
+    } else {
+        Box::new(data)
+    };
+    let mut archive = tar::Archive::new(reader.take(MAX_UNPACKED_SIZE));
+
+    let mut tags = Vec::new();
+    for entry in archive.entries().context("Not a valid tarball")? {
+        let mut entry = entry.context("Not a valid tarball")?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if path.extension().map_or(true, |x| x != "md") {
+            continue;
+        }
+        if tags.len() >= MAX_ARCHIVE_ENTRIES {
+            anyhow::bail!("The archive contains more than {} files", MAX_ARCHIVE_ENTRIES);
+        }
+        let mut text = String::new();
+        entry
+            .read_to_string(&mut text)
+            .with_context(|| format!("{} is not valid UTF-8", path.display()))?;
+        let tag =
+            TagFile::from_markdown(&text).with_context(|| format!("In {}", path.display()))?;
+        tags.push(tag);
+    }
+
+    let duplicates = tags.iter().map(|x| x.name.to_lowercase()).duplicates().join(", ");
+    if !duplicates.is_empty() {
+        anyhow::bail!("The archive contains these tags more than once: {}", duplicates);
+    }
+    Ok(tags)
+}
+
+/// Turn a tag name into something that's safe to use as a file name.
+fn file_name_for(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Parse a front matter value that's either a JSON string or plain text.
+fn parse_text(value: &str) -> Result<String> {
+    if value.starts_with('"') {
+        Ok(serde_json::from_str(value).context(format!("Invalid string: {}", value))?)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Parse a front matter value that's either a JSON list or comma separated plain text.
+fn parse_list(value: &str) -> Result<Vec<String>> {
+    if value.starts_with('[') {
+        Ok(serde_json::from_str(value).context(format!("Invalid list: {}", value))?)
+    } else {
+        Ok(value.split(',').map(|x| x.trim().to_string()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use robbb_db::tag::{RichTagField, TagLinkButton};
+
+    use super::*;
+
+    fn tag_file(name: &str) -> TagFile {
+        TagFile {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            category: None,
+            official: false,
+            templated: false,
+            author: None,
+            content: format!("Content of {}", name),
+            rich_content: None,
+        }
+    }
+
+    fn archive_paths(archive: &[u8]) -> Vec<String> {
+        tar::Archive::new(GzDecoder::new(archive))
+            .entries()
+            .unwrap()
+            .map(|x| x.unwrap().path().unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn markdown_round_trip() {
+        let tag = TagFile {
+            name: "rice: \"the\" guide".to_string(),
+            aliases: vec!["ricing".to_string(), "unixporn, but \"quoted\"".to_string()],
+            category: Some("Customization".to_string()),
+            official: true,
+            templated: true,
+            author: Some(UserId(1234567890)),
+            content: "Hey {user}\n---\nname: not front matter\n\n  indented".to_string(),
+            rich_content: Some(RichTagContent {
+                title: Some("Ricing".to_string()),
+                fields: vec![RichTagField {
+                    name: "Step 1".to_string(),
+                    value: "Pick a WM".to_string(),
+                    inline: true,
+                }],
+                color: Some(0xb8bb26),
+                buttons: vec![TagLinkButton {
+                    label: "Wiki".to_string(),
+                    url: "https://wiki.archlinux.org".to_string(),
+                }],
+                ..RichTagContent::default()
+            }),
+        };
+        let markdown = tag.to_markdown().unwrap();
+        assert_eq!(TagFile::from_markdown(&markdown).unwrap(), tag);
+    }
+
+    #[test]
+    fn markdown_round_trip_minimal() {
+        let tag = TagFile { content: String::new(), ..tag_file("empty") };
+        let markdown = tag.to_markdown().unwrap();
+        assert_eq!(TagFile::from_markdown(&markdown).unwrap(), tag);
+    }
+
+    #[test]
+    fn hand_written_files_are_community_tags() {
+        let tag = TagFile::from_markdown("---\nname: rice\n---\nSome content\n").unwrap();
+        assert_eq!(tag, TagFile { content: "Some content".to_string(), ..tag_file("rice") });
+    }
+
+    #[test]
+    fn plain_text_values() {
+        let text = "---\r\nname: rice\r\naliases: ricing, , unixporn\r\ncategory: \r\n---\r\nx";
+        let tag = TagFile::from_markdown(text).unwrap();
+        assert_eq!(tag.aliases, vec!["ricing", "unixporn"]);
+        assert_eq!(tag.category, None);
+        assert_eq!(tag.content, "x");
+    }
+
+    #[test]
+    fn invalid_front_matter() {
+        assert!(TagFile::from_markdown("name: rice\n").is_err());
+        assert!(TagFile::from_markdown("---\nname: rice\ncontent").is_err());
+        assert!(TagFile::from_markdown("---\naliases: rice\n---\n").is_err());
+        assert!(TagFile::from_markdown("---\nname: \"\"\n---\n").is_err());
+        assert!(TagFile::from_markdown("---\nname: rice\nofficial: yes\n---\n").is_err());
+        assert!(TagFile::from_markdown("---\nname: rice\nunknown: 1\n---\n").is_err());
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(file_name_for("../../etc/passwd"), "______etc_passwd");
+        assert_eq!(file_name_for("vim-vs_emacs"), "vim-vs_emacs");
+    }
+
+    #[test]
+    fn archive_round_trip_with_colliding_file_names() {
+        let tags = vec![tag_file("a/b"), tag_file("a?b"), tag_file("A b"), tag_file("a_b-2")];
+        let archive = write_archive(&tags).unwrap();
+        assert_eq!(
+            archive_paths(&archive),
+            vec!["tags/a_b.md", "tags/a_b-2.md", "tags/A_b-3.md", "tags/a_b-2-2.md"]
+        );
+        assert_eq!(read_archive(&archive).unwrap(), tags);
+    }
+
+    #[test]
+    fn archive_with_duplicate_tags() {
+        let archive = write_archive(&[tag_file("Rice"), tag_file("rice")]).unwrap();
+        assert!(read_archive(&archive).is_err());
+    }
+}
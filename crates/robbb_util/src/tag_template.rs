@@ -0,0 +1,211 @@
+//! A tiny template language for tag content.
+//!
+//! Templating has to be enabled per tag, so that tags containing code snippets
+//! are sent exactly as they were written.
+//! In templated tags, the supported placeholders are `{user}` (the user invoking the tag),
+//! `{channel}`, `{args}` (all arguments) and `{1}` to `{9}` (single arguments).
+//! `{{` and `}}` produce literal braces. Any other word in braces is refused when saving the tag.
+//! Rendering only ever substitutes text, and arguments are never interpreted as templates.
+//! `@everyone`, `@here` and role mentions are broken up when rendering any tag, so they never ping.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use poise::serenity_prelude::{ChannelId, Mentionable, UserId};
+
+/// Maximum amount of positional arguments a template can refer to.
+const MAX_POSITIONAL_ARGS: usize = 9;
+
+lazy_static::lazy_static! {
+    static ref PLACEHOLDER: regex::Regex = regex::Regex::new(r"\{\{|\}\}|\{([a-z0-9_]+)\}").unwrap();
+    static ref MASS_MENTION: regex::Regex = regex::Regex::new(r"@(everyone|here)|<@&\d+>").unwrap();
+    static ref USER_MENTION: regex::Regex = regex::Regex::new(r"<@!?(\d+)>").unwrap();
+}
+
+/// Values available to a template while rendering.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub user: UserId,
+    pub channel: ChannelId,
+    pub args: Vec<String>,
+}
+
+impl TemplateContext {
+    /// Split the given text into whitespace separated arguments.
+    pub fn new(user: UserId, channel: ChannelId, args: Option<&str>) -> Self {
+        let args = args.map(|x| x.split_whitespace().map(neutralize_mass_mentions).collect());
+        TemplateContext { user, channel, args: args.unwrap_or_default() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedTemplate {
+    pub text: String,
+    /// Users that were mentioned through arguments, and may be pinged.
+    pub mentioned_users: Vec<UserId>,
+}
+
+/// Check that templated content only uses known placeholders.
+pub fn validate_template(content: &str, templated: bool) -> Result<(), String> {
+    if !templated {
+        return Ok(());
+    }
+    let unknown_placeholders = PLACEHOLDER
+        .captures_iter(content)
+        .filter_map(|x| x.get(1))
+        .map(|x| x.as_str())
+        .filter(|x| !is_known_placeholder(x))
+        .unique()
+        .map(|x| format!("`{{{}}}`", x))
+        .collect_vec();
+    if !unknown_placeholders.is_empty() {
+        return Err(format!(
+            "Unknown placeholders {}. Available are `{{user}}`, `{{channel}}`, `{{args}}` and `{{1}}` to `{{{}}}`. Use `{{{{` and `}}}}` for literal braces",
+            unknown_placeholders.join(", "),
+            MAX_POSITIONAL_ARGS
+        ));
+    }
+    Ok(())
+}
+
+/// Render the content of a tag, substituting placeholders only if templating is enabled for it.
+pub fn render_tag_content(
+    content: &str,
+    templated: bool,
+    ctx: &TemplateContext,
+) -> RenderedTemplate {
+    if templated {
+        render_template(content, ctx)
+    } else {
+        RenderedTemplate { text: neutralize_mass_mentions(content), mentioned_users: Vec::new() }
+    }
+}
+
+pub fn render_template(content: &str, ctx: &TemplateContext) -> RenderedTemplate {
+    let text = PLACEHOLDER.replace_all(content, |caps: &regex::Captures| {
+        match caps.get(1).map(|x| x.as_str()) {
+            None if &caps[0] == "{{" => "{".to_string(),
+            None => "}".to_string(),
+            Some("user") => ctx.user.mention().to_string(),
+            Some("channel") => ctx.channel.mention().to_string(),
+            Some("args") => ctx.args.join(" "),
+            Some(name) => match positional_index(name) {
+                Some(idx) => ctx.args.get(idx).cloned().unwrap_or_default(),
+                None => caps[0].to_string(),
+            },
+        }
+    });
+    let text = neutralize_mass_mentions(&text);
+
+    let arg_users: HashSet<UserId> = ctx
+        .args
+        .iter()
+        .flat_map(|x| USER_MENTION.captures_iter(x))
+        .filter_map(|x| x[1].parse().ok().map(UserId))
+        .collect();
+    let mentioned_users = USER_MENTION
+        .captures_iter(&text)
+        .filter_map(|x| x[1].parse().ok().map(UserId))
+        .filter(|x| arg_users.contains(x))
+        .unique()
+        .collect();
+
+    RenderedTemplate { text, mentioned_users }
+}
+
+fn is_known_placeholder(name: &str) -> bool {
+    matches!(name, "user" | "channel" | "args") || positional_index(name).is_some()
+}
+
+/// Get the index into the arguments for a positional placeholder like `{1}`.
+fn positional_index(name: &str) -> Option<usize> {
+    name.parse::<usize>().ok().filter(|x| (1..=MAX_POSITIONAL_ARGS).contains(x)).map(|x| x - 1)
+}
+
+/// Break up `@everyone`, `@here` and role mentions, so they can't ping anyone.
+fn neutralize_mass_mentions(text: &str) -> String {
+    MASS_MENTION
+        .replace_all(text, |caps: &regex::Captures| caps[0].replacen('@', "@\u{200b}", 1))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(args: Option<&str>) -> TemplateContext {
+        TemplateContext::new(UserId(1), ChannelId(2), args)
+    }
+
+    #[test]
+    fn validate_accepts_known_placeholders() {
+        assert!(validate_template("{user} {channel} {args} {1} {9}", true).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_placeholders() {
+        let err = validate_template("hi {10} {name} {name}", true).unwrap_err();
+        assert!(err.contains("`{10}`, `{name}`."), "{}", err);
+    }
+
+    #[test]
+    fn validate_ignores_escaped_braces() {
+        assert!(validate_template("{{name}} and }}", true).is_ok());
+    }
+
+    #[test]
+    fn validate_ignores_braces_in_plain_tags() {
+        assert!(validate_template("fn main() { println!(\"{foo}\"); }", false).is_ok());
+    }
+
+    #[test]
+    fn validate_allows_mass_mentions() {
+        assert!(validate_template("don't ping @everyone or @here", false).is_ok());
+        assert!(validate_template("don't ping @everyone, {user}", true).is_ok());
+    }
+
+    #[test]
+    fn render_substitutes_placeholders() {
+        let rendered = render_template("{user} in {channel}: {args} / {2}", &ctx(Some("a b")));
+        assert_eq!(rendered.text, "<@1> in <#2>: a b / b");
+    }
+
+    #[test]
+    fn render_unescapes_braces() {
+        let rendered = render_template("{{user}} }} {{", &ctx(None));
+        assert_eq!(rendered.text, "{user} } {");
+    }
+
+    #[test]
+    fn render_leaves_out_of_range_positionals() {
+        let rendered = render_template("{10} {3}", &ctx(Some("a")));
+        assert_eq!(rendered.text, "{10} ");
+    }
+
+    #[test]
+    fn render_does_not_interpret_args() {
+        let rendered = render_template("{1} {2}", &ctx(Some("{user} {{")));
+        assert_eq!(rendered.text, "{user} {{");
+    }
+
+    #[test]
+    fn render_neutralizes_mass_mentions_in_args() {
+        let rendered = render_template("{args}", &ctx(Some("@everyone <@&3> @here")));
+        assert_eq!(rendered.text, "@\u{200b}everyone <@\u{200b}&3> @\u{200b}here");
+        assert!(rendered.mentioned_users.is_empty());
+    }
+
+    #[test]
+    fn render_only_pings_users_from_args() {
+        let rendered = render_template("<@5> {1} {1}", &ctx(Some("<@!4>")));
+        assert_eq!(rendered.text, "<@5> <@!4> <@!4>");
+        assert_eq!(rendered.mentioned_users, vec![UserId(4)]);
+    }
+
+    #[test]
+    fn render_plain_tags_unchanged_except_mass_mentions() {
+        let rendered = render_tag_content("{user} @everyone", false, &ctx(Some("<@4>")));
+        assert_eq!(rendered.text, "{user} @\u{200b}everyone");
+        assert!(rendered.mentioned_users.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use anyhow::{anyhow, Context, Result};
 use poise::serenity_prelude::GuildId;
 use serenity::{
     client,
+    http::StatusCode,
     model::{id::ChannelId, misc::EmojiIdentifier},
 };
 use std::env;
@@ -67,6 +68,17 @@ pub fn parse_required_env_var<E: Into<anyhow::Error>, T: std::str::FromStr<Err =
         .with_context(|| format!("Failed to parse env-var {}", key))
 }
 
+/// like [parse_required_env_var], but returns `None` if the variable is unset or empty.
+/// Values that fail to parse are still reported as errors.
+pub fn parse_optional_env_var<E: Into<anyhow::Error>, T: std::str::FromStr<Err = E>>(
+    key: &str,
+) -> Result<Option<T>> {
+    match env::var(key) {
+        Ok(value) if !value.trim().is_empty() => Ok(Some(parse_required_env_var(key)?)),
+        _ => Ok(None),
+    }
+}
+
 pub fn time_after_duration(duration: std::time::Duration) -> chrono::DateTime<chrono::Utc> {
     chrono::Utc::now()
         .checked_add_signed(chrono::Duration::from_std(duration).unwrap())
@@ -139,6 +151,14 @@ pub fn validate_url(value: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// Get the lowercase domain of a URL, if it is a valid URL.
+pub fn url_domain(value: &str) -> Option<String> {
+    if !validate_url(value) {
+        return None;
+    }
+    url::Url::parse(value).ok()?.domain().map(|x| x.trim_end_matches('.').to_lowercase())
+}
+
 pub fn pluralize(s: &str) -> String {
     if let Some(word) = s.strip_suffix("ys") {
         format!("{}ies", word)
@@ -162,6 +182,14 @@ pub fn is_image_file(s: &str) -> bool {
 }
 
 /// Return the bot version, as read from the VERSION environment variable at build time.
+/// Check whether an error is discord telling us that the requested thing doesn't exist (anymore).
+pub fn is_not_found(err: &serenity::Error) -> bool {
+    match err {
+        serenity::Error::Http(err) => err.status_code() == Some(StatusCode::NOT_FOUND),
+        _ => false,
+    }
+}
+
 pub fn bot_version() -> &'static str {
     option_env!("VERSION").unwrap_or("<no version>")
 }
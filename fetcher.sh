@@ -27,6 +27,39 @@ or to add an image to your fetch, be it your screenshot or wallpaper.
 EOF
 }
 
+# escape a value for use within a JSON string
+json_escape() {
+	printf '%s' "$1" | sed 's/\\/\\\\/g; s/"/\\"/g' | tr -d '\n\t'
+}
+
+print_json() {
+	fields=""
+	add_field() {
+		[ "$2" ] && fields="$fields${fields:+,}
+	\"$1\": \"$(json_escape "$2")\""
+	}
+	add_field distro "$NAME${ver:+ $ver}"
+	add_field kernel "$kernel"
+	add_field terminal "$term"
+	add_field editor "${EDITOR##*/}"
+	add_field shell "${SHELL##*/}"
+	add_field de_wm "$wm"
+	add_field bar "$bar"
+	add_field resolution "$resolution"
+	add_field display_protocol "$displayprot"
+	add_field gtk3_theme "$theme"
+	add_field gtk_icon_theme "$icons"
+	add_field cpu "$cpu"
+	add_field gpu "$gpu"
+	add_field memory "$ram"
+
+	printf '{%s\n}\n' "$fields"
+}
+
+# with --json, print the fetch as a file for /setfetch import instead of as a command
+output=print
+[ "$1" = --json ] && output=print_json
+
 kernel=$(uname -s)
 case $kernel in
 Linux)
@@ -176,7 +209,7 @@ Linux)
 
 	bar=${bar# }
 
-	print;;
+	$output;;
 Darwin)
 	NAME="macOS"
 
@@ -208,6 +241,6 @@ Darwin)
 		*) term="${TERM_PROGRAM%.app}";;
 	esac
 
-	print;;
+	$output;;
 *) echo "Unsupported OS; please add support on https://github.com/unixporn/robbb";;
 esac